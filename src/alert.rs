@@ -0,0 +1,243 @@
+use crate::cli::Config;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    process::Stdio,
+    sync::Mutex,
+};
+
+// Ended alerts kept in the history.
+const HISTORY: usize = 100;
+
+// Seconds of traffic the surge rule takes its mean over.
+const BASELINE_SECS: usize = 60;
+
+/// The conditions an alert is raised for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rule {
+    /// The rate is over a fixed threshold.
+    Rate,
+    /// The rate is a multiple of its recent mean.
+    Surge,
+    /// One application sends most of the traffic.
+    Hog,
+}
+
+impl Rule {
+    const ALL: [Rule; 3] = [Rule::Rate, Rule::Surge, Rule::Hog];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::Rate => "rate",
+            Rule::Surge => "surge",
+            Rule::Hog => "hog",
+        }
+    }
+}
+
+/// A storm, as told by one of the rules.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub rule: Rule,
+    /// What the rule saw when the alert was raised.
+    pub message: String,
+    /// Seconds since statspi started counting.
+    pub started: usize,
+    pub ended: Option<usize>,
+    /// Highest rate during the alert.
+    pub peak: u64,
+}
+
+// The rules, from the config. A threshold of zero turns its rule off.
+#[derive(Debug, Clone)]
+struct Thresholds {
+    rate: u64,
+    secs: u64,
+    factor: f64,
+    share: f64,
+    min_rate: u64,
+    hook: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct AlertState {
+    // Seconds in a row each rule's condition held.
+    held: [u64; Rule::ALL.len()],
+    active: [Option<Alert>; Rule::ALL.len()],
+    history: VecDeque<Alert>,
+    // Event totals per sender at the previous second.
+    last_totals: HashMap<String, u64>,
+}
+
+/// Watches the rates each second and raises alerts when a rule holds for long enough.
+#[derive(Debug)]
+pub struct Alerts {
+    thresholds: Thresholds,
+    state: Mutex<AlertState>,
+}
+
+impl Clone for Alerts {
+    fn clone(&self) -> Self {
+        Alerts {
+            thresholds: self.thresholds.clone(),
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl Alerts {
+    pub fn new(config: &Config) -> Alerts {
+        Alerts {
+            thresholds: Thresholds {
+                rate: config.alert_rate,
+                secs: config.alert_secs,
+                factor: config.alert_factor,
+                share: config.alert_share,
+                min_rate: config.alert_min_rate,
+                hook: config.alert_hook.clone(),
+            },
+            state: Mutex::new(AlertState::default()),
+        }
+    }
+
+    /// Check the rules against `second`, the second that just ended and the last of `recent`.
+    /// `talkers` are the event totals so far per sender, by bus name, with their names.
+    pub fn on_second(&self, second: usize, recent: &[u64], talkers: &[(String, String, u64)]) {
+        let Some(&rate) = recent.last() else {
+            return;
+        };
+        let rules = &self.thresholds;
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+
+        // The mean before the alert window, so a storm does not raise its own bar.
+        let end = recent.len().saturating_sub(rules.secs as usize + 1);
+        let before = &recent[end.saturating_sub(BASELINE_SECS)..end];
+        let baseline = before.iter().sum::<u64>() as f64 / before.len().max(1) as f64;
+
+        // The sender with the most events this second.
+        let mut top: Option<(&str, u64)> = None;
+        for (bus_name, name, total) in talkers.iter() {
+            let last = state.last_totals.insert(bus_name.clone(), *total);
+            let sent = total.saturating_sub(last.unwrap_or(*total));
+            if top.is_none_or(|(_, most)| sent > most) {
+                top = Some((name, sent));
+            }
+        }
+
+        let busy = rate >= rules.min_rate;
+        for (i, rule) in Rule::ALL.into_iter().enumerate() {
+            let holds = match rule {
+                Rule::Rate => (rules.rate > 0 && rate > rules.rate)
+                    .then(|| format!("{rate}/s, over {}/s", rules.rate)),
+                Rule::Surge => (rules.factor > 0.0
+                    && busy
+                    && !before.is_empty()
+                    && rate as f64 > rules.factor * baseline)
+                    .then(|| {
+                        format!(
+                            "{rate}/s, {:.1}× the mean of {baseline:.0}/s",
+                            rate as f64 / baseline.max(1.0)
+                        )
+                    }),
+                Rule::Hog => top
+                    .filter(|(_, sent)| {
+                        rules.share > 0.0
+                            && busy
+                            && *sent as f64 * 100.0 > rules.share * rate as f64
+                    })
+                    .map(|(name, sent)| {
+                        format!(
+                            "{name} sent {:.0}% of {rate}/s",
+                            sent as f64 * 100.0 / rate as f64
+                        )
+                    }),
+            };
+
+            match (holds, state.active[i].as_mut()) {
+                (Some(_), Some(alert)) => alert.peak = alert.peak.max(rate),
+                (Some(message), None) => {
+                    state.held[i] += 1;
+                    if state.held[i] >= rules.secs {
+                        let alert = Alert {
+                            rule,
+                            message,
+                            started: second,
+                            ended: None,
+                            peak: rate,
+                        };
+                        tracing::warn!("Alert, {}: {}", rule.name(), alert.message);
+                        if let Some(hook) = &rules.hook {
+                            run_hook(hook, &alert);
+                        }
+                        state.active[i] = Some(alert);
+                    }
+                }
+                (None, _) => {
+                    state.held[i] = 0;
+                    if let Some(mut alert) = state.active[i].take() {
+                        alert.ended = Some(second);
+                        if state.history.len() == HISTORY {
+                            state.history.pop_back();
+                        }
+                        state.history.push_front(alert);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The alerts raised that have not ended.
+    pub fn active(&self) -> Vec<Alert> {
+        let state = self.state.lock().unwrap();
+        state.active.iter().flatten().cloned().collect()
+    }
+
+    /// The alerts that ended, the latest first.
+    pub fn history(&self) -> Vec<Alert> {
+        self.state.lock().unwrap().history.iter().cloned().collect()
+    }
+}
+
+// Run the hook through the shell, telling it about the alert in the environment.
+// Its output is logged, printed it would garble the dashboard or the headless snapshots.
+fn run_hook(hook: &str, alert: &Alert) {
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .env("STATSPI_ALERT_RULE", alert.rule.name())
+        .env("STATSPI_ALERT_MESSAGE", &alert.message)
+        .env("STATSPI_ALERT_RATE", alert.peak.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    match child {
+        Ok(child) => {
+            tokio::spawn(async move {
+                match child.wait_with_output().await {
+                    Ok(output) => {
+                        let stdout = String::from_utf8_lossy(&output.stdout);
+                        let stderr = String::from_utf8_lossy(&output.stderr);
+                        if !stdout.trim().is_empty() {
+                            tracing::info!("Alert hook said: {}", stdout.trim());
+                        }
+                        if !output.status.success() {
+                            tracing::warn!(
+                                "Alert hook failed: {}: {}",
+                                output.status,
+                                stderr.trim()
+                            );
+                        } else if !stderr.trim().is_empty() {
+                            tracing::warn!("Alert hook complained: {}", stderr.trim());
+                        }
+                    }
+                    Err(e) => tracing::warn!("Alert hook failed: {e}"),
+                }
+            });
+        }
+        Err(e) => tracing::error!("Cannot run alert hook: {e}"),
+    }
+}
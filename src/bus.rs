@@ -1,47 +1,137 @@
-use crate::{Result, ACCESSIBLE_ROOT_PATH};
+use crate::{
+    category::{Category, CategoryCounts},
+    crawler::TreeStats,
+    histogram::Histogram,
+    AtspiEvent, Result, ACCESSIBLE_ROOT_PATH,
+};
 use atspi::{
+    events::CacheEvents,
     proxy::{accessible::AccessibleProxy, application::ApplicationProxy},
     Role,
 };
 use float_pretty_print::PrettyPrintFloat;
-use std::{fmt::Formatter, sync::Arc, time::Duration};
-use tokio::sync::Mutex as AsyncMutex;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt::Formatter,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc::Receiver, Mutex as AsyncMutex};
 use tokio::time::timeout;
-use zbus::{names::BusName, Connection, ProxyBuilder};
+use tokio_stream::StreamExt;
+use zbus::{fdo::DBusProxy, names::BusName, Connection, DBusError, ProxyBuilder};
+
+/// The percentiles of the response times that are tracked.
+pub const PERCENTILES: [(&str, f64); 4] =
+    [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("p99.9", 0.999)];
 
 #[derive(Debug, Clone, Default)]
 pub struct ResponseStats {
+    // Successful pings.
     pub samples: u32,
+    // Failed pings.
+    pub timeouts: u32,
+    pub errors: BTreeMap<String, u32>,
+    pub last_responsive: Option<Instant>,
+
     pub sum: Duration,
     pub min: Option<Duration>,
     pub max: Option<Duration>,
     pub mean: Option<Duration>,
-    pub sosd: u128, // sum of squared differences
     pub std_dev: Option<Duration>,
+
+    // Running mean and sum of squared differences from it, in nanoseconds (Welford).
+    mean_nanos: f64,
+    m2: f64,
+
+    // For the percentiles.
+    histogram: Histogram,
+}
+
+impl ResponseStats {
+    pub fn add(&mut self, res: Duration) {
+        if self.min.is_none() || res < self.min.unwrap() {
+            self.min.replace(res);
+        }
+        if self.max.is_none() || res > self.max.unwrap() {
+            self.max.replace(res);
+        }
+
+        self.sum += res;
+        self.samples += 1;
+        self.last_responsive = Some(Instant::now());
+
+        // Welford's online algorithm: the squared differences are taken from both the
+        // previous and the updated mean, which keeps the variance exact as the mean moves.
+        let nanos = res.as_nanos() as f64;
+        let delta = nanos - self.mean_nanos;
+        self.mean_nanos += delta / f64::from(self.samples);
+        self.m2 += delta * (nanos - self.mean_nanos);
+
+        self.mean
+            .replace(Duration::from_nanos(self.mean_nanos.round() as u64));
+
+        let variance_nanos = self.m2 / f64::from(self.samples);
+        let std_dev = variance_nanos.sqrt().round() as u64;
+        self.std_dev.replace(Duration::from_nanos(std_dev));
+
+        self.histogram.record(res);
+    }
+
+    /// The response time below which a fraction `q` of the responses came in.
+    pub fn percentile(&self, q: f64) -> Option<Duration> {
+        // A bucket's midpoint may lie outside the range actually seen.
+        let value = self.histogram.quantile(q)?;
+        Some(value.clamp(self.min?, self.max?))
+    }
+
+    /// Pings that went unanswered or failed.
+    pub fn failures(&self) -> u32 {
+        self.timeouts + self.errors.values().sum::<u32>()
+    }
+
+    /// Percentage of the pings that were answered, `None` if there were none yet.
+    pub fn availability(&self) -> Option<f64> {
+        let probes = self.samples + self.failures();
+        (probes > 0).then(|| f64::from(self.samples) * 100.0 / f64::from(probes))
+    }
+
+    /// Pretty print the availability.
+    pub fn health(&self) -> Health<'_> {
+        Health(self)
+    }
+
+    /// Pretty print the percentiles.
+    pub fn percentiles(&self) -> Percentiles<'_> {
+        Percentiles(self)
+    }
+}
+
+// Convert to the appropriate unit. Three digits and three decimals.
+fn to_pretty(duration: Duration) -> String {
+    if duration.as_secs() > 0 {
+        let millis = duration.as_millis() as f64 / 1_000.0;
+        format!("{:10}s", PrettyPrintFloat(millis))
+    } else if duration.as_millis() > 0 {
+        let micros = duration.as_micros() as f64 / 1_000.0;
+        format!("{:10}ms", PrettyPrintFloat(micros))
+    } else if duration.as_micros() > 0 {
+        let nanos = duration.as_nanos() as f64 / 1_000.0;
+        format!("{:10}us", PrettyPrintFloat(nanos))
+    } else if duration.as_nanos() > 0 {
+        let nanos = duration.as_nanos();
+        format!("{:10}ns", nanos)
+    } else {
+        format!("{:10}ns", PrettyPrintFloat(0.0f64))
+    }
 }
 
 // Pretty print ResponseTimeStats.:
 impl std::fmt::Display for ResponseStats {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        // Convert to the appropriate unit. Three digits and three decimals.
-        let to_pretty = |duration: Duration| -> String {
-            if duration.as_secs() > 0 {
-                let millis = duration.as_millis() as f64 / 1_000.0;
-                format!("{:10}s", PrettyPrintFloat(millis))
-            } else if duration.as_millis() > 0 {
-                let micros = duration.as_micros() as f64 / 1_000.0;
-                format!("{:10}ms", PrettyPrintFloat(micros))
-            } else if duration.as_micros() > 0 {
-                let nanos = duration.as_nanos() as f64 / 1_000.0;
-                format!("{:10}us", PrettyPrintFloat(nanos))
-            } else if duration.as_nanos() > 0 {
-                let nanos = duration.as_nanos();
-                format!("{:10}ns", nanos)
-            } else {
-                format!("{:10}ns", PrettyPrintFloat(0.0f64))
-            }
-        };
-
         let min = self.min.unwrap_or(Duration::from_secs(0));
         let max = self.max.unwrap_or(Duration::from_secs(0));
         let mean = self.mean.unwrap_or(Duration::from_secs(0));
@@ -58,18 +148,118 @@ impl std::fmt::Display for ResponseStats {
     }
 }
 
-#[derive(Debug)]
+/// How well a server answers pings, see [`ResponseStats::health`].
+pub struct Health<'a>(&'a ResponseStats);
+
+impl std::fmt::Display for Health<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let stats = self.0;
+        let Some(availability) = stats.availability() else {
+            return write!(f, "not measured yet");
+        };
+
+        write!(f, "available: {availability:.1}% ")?;
+        match stats.last_responsive {
+            Some(at) => write!(f, "last responsive: {}s ago", at.elapsed().as_secs())?,
+            None => write!(f, "never responsive")?,
+        }
+        write!(f, " timeouts: {}", stats.timeouts)?;
+        for (name, count) in stats.errors.iter() {
+            write!(f, " {name}: {count}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The percentiles of a server's response times, see [`ResponseStats::percentiles`].
+pub struct Percentiles<'a>(&'a ResponseStats);
+
+impl std::fmt::Display for Percentiles<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (label, q)) in PERCENTILES.iter().enumerate() {
+            let value = self.0.percentile(*q).unwrap_or(Duration::from_secs(0));
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{label}: {}", to_pretty(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Number of response times kept per server: ten minutes at the default ping interval.
+const RTT_HISTORY: usize = 300;
+
+// Time a joining application gets to tell its name, the membership changes wait meanwhile.
+const CONNECT_DEADLINE: Duration = Duration::from_secs(2);
+
+// Wait before asking a sender that did not tell its name again, doubled after each try.
+const RETRY_FIRST: Duration = Duration::from_secs(5);
+const RETRY_MAX: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone)]
 pub struct Server {
     pub accessible_name: String,
     pub bus_name: zbus::names::OwnedBusName,
     pub accessible_proxy: AccessibleProxy<'static>,
+    #[allow(dead_code)]
     pub application_proxy: ApplicationProxy<'static>,
 
     pub stats: ResponseStats,
+
+    /// The latest response times, oldest first, `None` for pings that failed.
+    pub rtt_history: VecDeque<Option<Duration>>,
+
+    /// When the server left the bus.
+    pub departed: Option<Instant>,
+
+    /// The size of the server's accessible tree, once the crawler got to it.
+    pub tree: Option<TreeStats>,
 }
 
 #[allow(dead_code)]
 impl Server {
+    /// Connect to the accessible application at bus name `name`.
+    /// `None` if it does not look like one.
+    pub async fn connect(conn: &Connection, name: &str) -> Result<Option<Server>> {
+        let accessible_proxy: AccessibleProxy = ProxyBuilder::new(conn)
+            .interface("org.a11y.atspi.Accessible")?
+            .path(ACCESSIBLE_ROOT_PATH)?
+            .destination(name.to_string())?
+            .build()
+            .await?;
+
+        // Skip if the accessible application does not expose a `name` property.
+        let Ok(accessible_name) = accessible_proxy.name().await else {
+            return Ok(None);
+        };
+
+        let Ok(application_proxy) = zbus::ProxyBuilder::new(conn)
+            .interface("org.a11y.atspi.Application")?
+            .path(ACCESSIBLE_ROOT_PATH)?
+            .destination(name.to_string())?
+            .build()
+            .await
+        else {
+            return Ok(None);
+        };
+
+        let bus_name = BusName::try_from(name.to_string())?;
+
+        Ok(Some(Server {
+            accessible_name,
+            bus_name: bus_name.into(),
+            accessible_proxy,
+            application_proxy,
+            stats: ResponseStats::default(),
+            rtt_history: VecDeque::with_capacity(RTT_HISTORY),
+            departed: None,
+            tree: None,
+        }))
+    }
+
     pub async fn get_role(&self) -> zbus::Result<Role> {
         self.accessible_proxy.get_role().await
     }
@@ -80,47 +270,168 @@ impl Server {
         self.accessible_proxy.name().await
     }
 
-    pub async fn acquire_rtt(&self) -> Option<Duration> {
-        let deadline = Duration::from_millis(50);
+    /// Time a `get_role` call, if it completes within `deadline`.
+    pub async fn acquire_rtt(&self, deadline: Duration) -> Probe {
         let start = std::time::Instant::now();
 
-        if timeout(deadline, self.get_role()).await.is_ok() {
-            return Some(start.elapsed());
+        match timeout(deadline, self.get_role()).await {
+            Ok(Ok(_)) => Probe::Responded(start.elapsed()),
+            Ok(Err(e)) => Probe::Failed(error_name(&e)),
+            Err(_) => Probe::TimedOut,
         }
-
-        None
     }
 
-    pub fn update_rtt_stats(&mut self, res: Duration) {
-        if self.stats.min.is_none() || res < self.stats.min.unwrap() {
-            self.stats.min.replace(res);
+    pub fn update_rtt_stats(&mut self, probe: Probe) {
+        if self.rtt_history.len() == RTT_HISTORY {
+            self.rtt_history.pop_front();
         }
-        if self.stats.max.is_none() || res > self.stats.max.unwrap() {
-            self.stats.max.replace(res);
+        self.rtt_history.push_back(match probe {
+            Probe::Responded(res) => Some(res),
+            _ => None,
+        });
+
+        match probe {
+            Probe::Responded(res) => self.stats.add(res),
+            Probe::TimedOut => self.stats.timeouts += 1,
+            Probe::Failed(name) => *self.stats.errors.entry(name).or_default() += 1,
         }
+    }
+}
 
-        self.stats.sum += res;
-        self.stats.samples += 1;
+/// The outcome of pinging a server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Probe {
+    Responded(Duration),
+    TimedOut,
+    /// The call failed, with this D-Bus error name.
+    Failed(String),
+}
 
-        let mean = self.stats.sum / self.stats.samples;
-        self.stats.mean.replace(mean);
+// The D-Bus error name, or a description for errors that did not come off the bus.
+pub fn error_name(error: &zbus::Error) -> String {
+    match error {
+        zbus::Error::MethodError(name, _, _) => name.to_string(),
+        zbus::Error::FDO(e) => e.name().to_string(),
+        e => e.to_string(),
+    }
+}
 
-        // let diff = res.abs_diff(mean); // unstable feature
-        let diff = if res > mean { res - mean } else { mean - res };
+/// The events an application sent, counted per category.
+#[derive(Debug)]
+pub struct Talker {
+    pub accessible_name: String,
+    pub bus_name: String,
+    pub counts: CategoryCounts,
+    /// The application has left the bus.
+    pub departed: AtomicBool,
+}
 
-        // calculate sum of squared differences, "sosd"
-        self.stats.sosd += diff.as_nanos() * diff.as_nanos();
+impl Clone for Talker {
+    fn clone(&self) -> Self {
+        Talker {
+            accessible_name: self.accessible_name.clone(),
+            bus_name: self.bus_name.clone(),
+            counts: self.counts.clone(),
+            departed: AtomicBool::new(self.has_departed()),
+        }
+    }
+}
 
-        let variance_nanos = self.stats.sosd as f64 / self.stats.samples as f64;
+impl Talker {
+    fn new(accessible_name: &str, bus_name: &str) -> Talker {
+        Talker {
+            accessible_name: accessible_name.to_string(),
+            bus_name: bus_name.to_string(),
+            counts: CategoryCounts::default(),
+            departed: AtomicBool::new(false),
+        }
+    }
 
-        let std_dev = variance_nanos.sqrt().round() as u64;
-        self.stats.std_dev.replace(Duration::from_nanos(std_dev));
+    pub fn has_departed(&self) -> bool {
+        self.departed.load(Ordering::Acquire)
+    }
+}
+
+/// An application joining or leaving the bus, as told by the events or the bus itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Membership {
+    Joined(String),
+    Left(String),
+}
+
+impl Membership {
+    /// Applications announce their objects to the cache, their root object included.
+    /// The root object of an application being removed means the application is leaving.
+    pub fn of(event: &AtspiEvent) -> Option<Membership> {
+        match event {
+            AtspiEvent::Cache(CacheEvents::Add(add)) => {
+                Some(Membership::Joined(add.node_added.app.name.to_string()))
+            }
+            AtspiEvent::Cache(CacheEvents::LegacyAdd(add)) => {
+                Some(Membership::Joined(add.node_added.app.name.to_string()))
+            }
+            AtspiEvent::Cache(CacheEvents::Remove(remove))
+                if remove.node_removed.path.as_str() == ACCESSIBLE_ROOT_PATH.as_str() =>
+            {
+                Some(Membership::Left(remove.node_removed.name.to_string()))
+            }
+            _ => None,
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct Servers {
-    pub bus: Vec<Arc<AsyncMutex<Server>>>,
+    // The servers on the bus, these get pinged.
+    pub bus: RwLock<Vec<Arc<AsyncMutex<Server>>>>,
+
+    // Servers that left the bus, kept for their history.
+    pub departed: RwLock<Vec<Arc<AsyncMutex<Server>>>>,
+
+    // Event senders by bus name, so events can be attributed without locking a `Server`.
+    pub talkers: RwLock<HashMap<String, Arc<Talker>>>,
+
+    // Events from senders that are not among the bus servers.
+    pub unresolved: Talker,
+}
+
+impl Default for Servers {
+    fn default() -> Self {
+        Servers {
+            bus: RwLock::new(Vec::new()),
+            departed: RwLock::new(Vec::new()),
+            talkers: RwLock::new(HashMap::new()),
+            unresolved: Talker::new("(unresolved)", ""),
+        }
+    }
+}
+
+// A copy of the servers as they are now. A server being pinged cannot be copied,
+// the copy shares it with the original.
+impl Clone for Servers {
+    fn clone(&self) -> Self {
+        let copy = |servers: &RwLock<Vec<Arc<AsyncMutex<Server>>>>| {
+            let servers = servers.read().unwrap();
+            let copies = servers.iter().map(|server| match server.try_lock() {
+                Ok(guard) => Arc::new(AsyncMutex::new(guard.clone())),
+                Err(_) => Arc::clone(server),
+            });
+            RwLock::new(copies.collect())
+        };
+        let talkers = self.talkers.read().unwrap();
+
+        Servers {
+            bus: copy(&self.bus),
+            departed: copy(&self.departed),
+            talkers: RwLock::new(
+                talkers
+                    .iter()
+                    .map(|(name, talker)| (name.clone(), Arc::new(Talker::clone(talker))))
+                    .collect(),
+            ),
+            unresolved: self.unresolved.clone(),
+        }
+    }
 }
 
 impl Servers {
@@ -132,56 +443,105 @@ impl Servers {
             .build()
             .await?;
 
+        let servers = Servers::default();
+
         // Registry considers all accessible programs on the bus its children.
         let a11ies = registry_as_accessible.get_children().await?;
-        let mut bus: Vec<Arc<AsyncMutex<Server>>> = Vec::with_capacity(a11ies.len());
 
         for a11y in a11ies {
             let name = a11y.name.clone();
-            let name = name.trim().to_string(); // Remove whitespace.
-            let accessible_proxy: AccessibleProxy = ProxyBuilder::new(conn)
-                .interface("org.a11y.atspi.Accessible")?
-                .path(ACCESSIBLE_ROOT_PATH)?
-                .destination(name.clone())?
-                .build()
-                .await?;
-
-            // Skip if the accessible application does not expose a `name` property.
-            let Ok(accessible_name) = accessible_proxy.name().await else {
-                continue;
-            };
+            let name = name.trim(); // Remove whitespace.
 
-            let Ok(application_proxy) = zbus::ProxyBuilder::new(conn)
-                .interface("org.a11y.atspi.Application")?
-                .path(ACCESSIBLE_ROOT_PATH)?
-                .destination(name.clone())?
-                .build()
-                .await
-            else {
-                continue;
-            };
+            if let Some(server) = Server::connect(conn, name).await? {
+                servers.add_server(server);
+            }
+        }
 
-            let bus_name = BusName::try_from(a11y.name.clone())?;
+        Ok(servers)
+    }
 
-            let server = Server {
-                accessible_name,
-                bus_name: bus_name.into(),
-                accessible_proxy,
-                application_proxy,
-                stats: ResponseStats::default(),
-            };
+    /// Servers as named in a recording.
+    /// There is no one to ping, but events can still be attributed.
+    pub fn replayed(names: &[(String, String)]) -> Servers {
+        let talkers = names
+            .iter()
+            .map(|(bus_name, accessible_name)| {
+                let talker = Talker::new(accessible_name, bus_name);
+                (bus_name.clone(), Arc::new(talker))
+            })
+            .collect();
 
-            let server = Arc::new(AsyncMutex::new(server));
-            bus.push(server);
+        Servers {
+            talkers: RwLock::new(talkers),
+            ..Servers::default()
         }
+    }
 
-        Ok(Servers { bus })
+    /// The servers on the bus.
+    pub fn live(&self) -> Vec<Arc<AsyncMutex<Server>>> {
+        self.bus.read().unwrap().clone()
     }
 
-    #[allow(dead_code)]
-    pub fn get_server(&self, name: &str) -> Option<Arc<AsyncMutex<Server>>> {
-        for server in self.bus.iter() {
-            let guard = server.blocking_lock();
+    /// The servers that left the bus.
+    pub fn gone(&self) -> Vec<Arc<AsyncMutex<Server>>> {
+        self.departed.read().unwrap().clone()
+    }
+
+    /// The event senders, departed or not.
+    pub fn talkers(&self) -> Vec<Arc<Talker>> {
+        self.talkers.read().unwrap().values().cloned().collect()
+    }
+
+    /// Whether events from `name` are attributed to a server.
+    pub fn is_known(&self, name: &str) -> bool {
+        self.talkers.read().unwrap().contains_key(name)
+    }
+
+    /// The accessible name of the server at bus name `name`.
+    pub fn accessible_name(&self, name: &str) -> Option<String> {
+        let talkers = self.talkers.read().unwrap();
+        talkers
+            .get(name)
+            .map(|talker| talker.accessible_name.clone())
+    }
+
+    /// Zero the event counts of all senders, in `category` only if one is given.
+    pub fn reset_counts(&self, category: Option<Category>) {
+        let talkers = self.talkers.read().unwrap();
+        for talker in talkers.values().map(Arc::as_ref).chain([&self.unresolved]) {
+            talker.counts.reset(category);
+        }
+    }
+
+    /// Attribute an event of `category` to the server that sent it.
+    pub fn count_event(&self, sender: Option<&str>, category: Category) {
+        let talkers = self.talkers.read().unwrap();
+        match sender.and_then(|sender| talkers.get(sender)) {
+            Some(talker) => talker.counts.incr(category),
+            None => self.unresolved.counts.incr(category),
+        }
+    }
+
+    pub fn add_server(&self, server: Server) {
+        self.add_talker(server.bus_name.as_str(), &server.accessible_name);
+        self.bus
+            .write()
+            .unwrap()
+            .push(Arc::new(AsyncMutex::new(server)));
+    }
+
+    /// Attribute the events of `bus_name` from now on, as `accessible_name`.
+    pub fn add_talker(&self, bus_name: &str, accessible_name: &str) {
+        self.talkers
+            .write()
+            .unwrap()
+            .entry(bus_name.to_string())
+            .or_insert_with(|| Arc::new(Talker::new(accessible_name, bus_name)));
+    }
+
+    pub async fn get_server(&self, name: &str) -> Option<Arc<AsyncMutex<Server>>> {
+        for server in self.live() {
+            let guard = server.lock().await;
             if guard.bus_name == name {
                 return Some(server.clone());
             }
@@ -189,11 +549,138 @@ impl Servers {
         None
     }
 
-    #[allow(dead_code)]
-    pub fn remove_server(&mut self, name: &str) {
-        self.bus.retain(|server| {
-            let guard = server.blocking_lock();
-            guard.bus_name != name
-        });
+    /// Take the server off the bus. Its history is kept with the departed servers.
+    pub async fn remove_server(&self, name: &str) {
+        if let Some(talker) = self.talkers.read().unwrap().get(name) {
+            talker.departed.store(true, Ordering::Release);
+        }
+
+        let Some(server) = self.get_server(name).await else {
+            return;
+        };
+        server.lock().await.departed = Some(Instant::now());
+
+        self.bus
+            .write()
+            .unwrap()
+            .retain(|other| !Arc::ptr_eq(other, &server));
+        self.departed.write().unwrap().push(server);
+    }
+
+    /// Follow applications joining and leaving the bus.
+    /// Memberships are read from the events and from the names the bus itself tells come and go.
+    pub async fn track(
+        &self,
+        conn: &Connection,
+        mut memberships: Receiver<Membership>,
+    ) -> Result<()> {
+        let dbus = DBusProxy::new(conn).await?;
+        let mut owner_changes = dbus.receive_name_owner_changed().await?;
+
+        // Senders that did not tell their name, with when to ask again and the wait after that.
+        let mut rejected: HashMap<String, (Instant, Duration)> = HashMap::new();
+
+        loop {
+            let membership = tokio::select! {
+                Some(membership) = memberships.recv() => membership,
+                Some(change) = owner_changes.next() => {
+                    let Ok(args) = change.args() else {
+                        continue;
+                    };
+                    let name = args.name().to_string();
+                    match args.new_owner().as_ref() {
+                        None => Membership::Left(name),
+                        // Events are sent from unique names, like ":1.42".
+                        Some(_) if name.starts_with(':') => Membership::Joined(name),
+                        Some(_) => continue,
+                    }
+                }
+                else => return Ok(()),
+            };
+
+            match membership {
+                Membership::Joined(name) => {
+                    if self.is_known(&name)
+                        || rejected
+                            .get(&name)
+                            .is_some_and(|(at, _)| Instant::now() < *at)
+                    {
+                        continue;
+                    }
+                    // An application that does not answer in time stays unresolved,
+                    // it is asked again at a later membership or signal of it.
+                    match timeout(CONNECT_DEADLINE, Server::connect(conn, &name)).await {
+                        Ok(Ok(Some(server))) => {
+                            rejected.remove(&name);
+                            self.add_server(server);
+                        }
+                        Ok(Ok(None) | Err(_)) | Err(_) => {
+                            let wait = rejected
+                                .get(&name)
+                                .map_or(RETRY_FIRST, |(_, wait)| (*wait * 2).min(RETRY_MAX));
+                            rejected.insert(name, (Instant::now() + wait, wait));
+                        }
+                    }
+                }
+                Membership::Left(name) => {
+                    rejected.remove(&name);
+                    if self.is_known(&name) {
+                        self.remove_server(&name).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_of(millis: &[u64]) -> ResponseStats {
+        let mut stats = ResponseStats::default();
+        for ms in millis {
+            stats.add(Duration::from_millis(*ms));
+        }
+        stats
+    }
+
+    #[test]
+    fn mean_and_standard_deviation() {
+        // Mean 5, population variance 4.
+        let stats = stats_of(&[2, 4, 4, 4, 5, 5, 7, 9]);
+
+        assert_eq!(stats.samples, 8);
+        assert_eq!(stats.mean, Some(Duration::from_millis(5)));
+        assert_eq!(stats.std_dev, Some(Duration::from_millis(2)));
+        assert_eq!(stats.min, Some(Duration::from_millis(2)));
+        assert_eq!(stats.max, Some(Duration::from_millis(9)));
+    }
+
+    #[test]
+    fn variance_of_a_large_offset() {
+        // Large values with a small spread lose precision in the naive sum of squares.
+        let stats = stats_of(&[1_000_000, 1_000_001, 1_000_002]);
+
+        assert_eq!(stats.mean, Some(Duration::from_millis(1_000_001)));
+        // The population standard deviation of 0, 1, 2 ms is sqrt(2/3) ms.
+        let std_dev = stats.std_dev.unwrap().as_nanos() as f64;
+        assert!((std_dev - (2.0f64 / 3.0).sqrt() * 1e6).abs() < 1.0);
+    }
+
+    #[test]
+    fn a_single_sample_has_no_spread() {
+        let stats = stats_of(&[3]);
+
+        assert_eq!(stats.mean, Some(Duration::from_millis(3)));
+        assert_eq!(stats.std_dev, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn percentiles_stay_within_the_range_seen() {
+        let stats = stats_of(&[10, 10, 10, 10]);
+
+        assert_eq!(stats.percentile(0.5), Some(Duration::from_millis(10)));
+        assert_eq!(stats.percentile(0.99), Some(Duration::from_millis(10)));
     }
 }
@@ -0,0 +1,286 @@
+use crate::{AtspiEvent, Counter, Result};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Mutex,
+};
+
+/// The signal categories statspi keeps score of.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Category {
+    #[default]
+    Keyboard,
+    Mouse,
+    Focus,
+    Window,
+    Document,
+    Object,
+    Terminal,
+    Cache,
+    Listeners,
+    Available,
+    Other,
+    Error,
+}
+
+impl Category {
+    pub const COUNT: usize = 12;
+
+    pub const ALL: [Category; Category::COUNT] = [
+        Category::Keyboard,
+        Category::Mouse,
+        Category::Focus,
+        Category::Window,
+        Category::Document,
+        Category::Object,
+        Category::Terminal,
+        Category::Cache,
+        Category::Listeners,
+        Category::Available,
+        Category::Other,
+        Category::Error,
+    ];
+
+    /// Categorize an event as it comes off the bus.
+    pub fn of(event: &Result<AtspiEvent>) -> Category {
+        match event {
+            Ok(AtspiEvent::Mouse(_)) => Category::Mouse,
+            Ok(AtspiEvent::Keyboard(_)) => Category::Keyboard,
+            Ok(AtspiEvent::Focus(_)) => Category::Focus,
+            Ok(AtspiEvent::Window(_)) => Category::Window,
+            Ok(AtspiEvent::Document(_)) => Category::Document,
+            Ok(AtspiEvent::Object(_)) => Category::Object,
+            Ok(AtspiEvent::Terminal(_)) => Category::Terminal,
+            Ok(AtspiEvent::Cache(_)) => Category::Cache,
+            Ok(AtspiEvent::Listener(_)) => Category::Listeners,
+            Ok(AtspiEvent::Available(_)) => Category::Available,
+            Ok(_) => Category::Other,
+            Err(_) => Category::Error,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Keyboard => "Keyboard",
+            Category::Mouse => "Mouse",
+            Category::Focus => "Focus",
+            Category::Window => "Window",
+            Category::Document => "Document",
+            Category::Object => "Object",
+            Category::Terminal => "Terminal",
+            Category::Cache => "Cache",
+            Category::Listeners => "Listeners",
+            Category::Available => "Available",
+            Category::Other => "Other",
+            Category::Error => "Error",
+        }
+    }
+
+    /// The category after this one, wrapping around.
+    pub fn next(&self) -> Category {
+        Category::ALL[(self.index() + 1) % Category::COUNT]
+    }
+
+    /// The category before this one, wrapping around.
+    pub fn prev(&self) -> Category {
+        Category::ALL[(self.index() + Category::COUNT - 1) % Category::COUNT]
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// A counter per category.
+#[derive(Debug, Clone, Default)]
+pub struct CategoryCounts {
+    counters: [Counter; Category::COUNT],
+}
+
+impl CategoryCounts {
+    pub fn get(&self, category: Category) -> &Counter {
+        &self.counters[category.index()]
+    }
+
+    pub fn incr(&self, category: Category) {
+        self.get(category).incr();
+    }
+
+    /// Sum of all categories.
+    pub fn total(&self) -> u64 {
+        self.counters.iter().map(Counter::load).sum()
+    }
+
+    /// Zero `category`, or all categories if none is given.
+    pub fn reset(&self, category: Option<Category>) {
+        match category {
+            Some(category) => {
+                self.get(category).reset();
+            }
+            None => self.counters.iter().for_each(|counter| {
+                counter.reset();
+            }),
+        }
+    }
+}
+
+/// Events with the same signal member, e.g. "StateChanged" within the Object category.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemberTally {
+    pub total: u64,
+    /// Events in the last complete second.
+    pub rate: u64,
+    this_second: u64,
+}
+
+/// Second-level counters: per category, a tally per signal member.
+#[derive(Debug, Default)]
+pub struct MemberTallies {
+    members: Mutex<HashMap<Category, BTreeMap<String, MemberTally>>>,
+}
+
+impl Clone for MemberTallies {
+    fn clone(&self) -> Self {
+        MemberTallies {
+            members: Mutex::new(self.members.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl MemberTallies {
+    pub fn count(&self, category: Category, member: &str) {
+        let mut members = self.members.lock().unwrap();
+        let tally = members
+            .entry(category)
+            .or_default()
+            .entry(member.to_string())
+            .or_default();
+        tally.total += 1;
+        tally.this_second += 1;
+    }
+
+    /// Roll the per-second counts over into the rates.
+    pub fn on_second(&self) {
+        let mut members = self.members.lock().unwrap();
+        for tally in members.values_mut().flat_map(BTreeMap::values_mut) {
+            tally.rate = std::mem::take(&mut tally.this_second);
+        }
+    }
+
+    /// Forget the members of `category`, or of all categories if none is given.
+    pub fn reset(&self, category: Option<Category>) {
+        let mut members = self.members.lock().unwrap();
+        match category {
+            Some(category) => {
+                members.remove(&category);
+            }
+            None => members.clear(),
+        }
+    }
+
+    /// The members seen in `category`, by name.
+    pub fn of(&self, category: Category) -> Vec<(String, MemberTally)> {
+        let members = self.members.lock().unwrap();
+        members
+            .get(&category)
+            .map(|members| {
+                members
+                    .iter()
+                    .map(|(name, tally)| (name.clone(), *tally))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct SeriesState {
+    // Category totals at the end of the previous second.
+    last: [u64; Category::COUNT],
+    seconds: VecDeque<[u64; Category::COUNT]>,
+}
+
+/// Events per category in each of the recent seconds, so the mix of the traffic can be followed.
+#[derive(Debug)]
+pub struct CategorySeries {
+    capacity: usize,
+    state: Mutex<SeriesState>,
+}
+
+impl Clone for CategorySeries {
+    fn clone(&self) -> Self {
+        CategorySeries {
+            capacity: self.capacity,
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl CategorySeries {
+    pub fn new(capacity: usize) -> CategorySeries {
+        CategorySeries {
+            capacity,
+            state: Mutex::new(SeriesState {
+                seconds: VecDeque::with_capacity(capacity),
+                ..SeriesState::default()
+            }),
+        }
+    }
+
+    /// Record the second that just ended, given the category totals so far.
+    pub fn on_second(&self, totals: [u64; Category::COUNT]) {
+        let mut state = self.state.lock().unwrap();
+        let mut second = [0; Category::COUNT];
+        for (i, total) in totals.iter().enumerate() {
+            second[i] = total.saturating_sub(state.last[i]);
+        }
+        state.last = totals;
+
+        if state.seconds.len() == self.capacity {
+            state.seconds.pop_front();
+        }
+        state.seconds.push_back(second);
+    }
+
+    /// Follow the totals of `category`, or of all categories if none is given, from zero,
+    /// after their counters were reset.
+    pub fn reset(&self, category: Option<Category>) {
+        let mut state = self.state.lock().unwrap();
+        match category {
+            Some(category) => state.last[category.index()] = 0,
+            None => state.last = [0; Category::COUNT],
+        }
+    }
+
+    /// The seconds kept, the oldest first, each indexed like `Category::ALL`.
+    pub fn seconds(&self) -> Vec<[u64; Category::COUNT]> {
+        self.state.lock().unwrap().seconds.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_mix_counts_the_second_of_a_reset() {
+        let series = CategorySeries::new(10);
+        let mut totals = [0; Category::COUNT];
+        totals[Category::Object.index()] = 100;
+        totals[Category::Focus.index()] = 7;
+        series.on_second(totals);
+
+        // The Object counter was reset, then counted 4 more events in the same second.
+        series.reset(Some(Category::Object));
+        totals[Category::Object.index()] = 4;
+        totals[Category::Focus.index()] = 9;
+        series.on_second(totals);
+
+        let last = *series.seconds().last().unwrap();
+        assert_eq!(last[Category::Object.index()], 4);
+        assert_eq!(last[Category::Focus.index()], 2);
+
+        series.reset(None);
+        series.on_second([1; Category::COUNT]);
+        assert_eq!(*series.seconds().last().unwrap(), [1; Category::COUNT]);
+    }
+}
@@ -0,0 +1,479 @@
+use crate::{filter::Filter, headless::OutputFormat, ui::Preset, Result};
+use clap::{Args, Parser};
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Statistics on the traffic of the AT-SPI2 accessibility bus.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Read the tunables from this TOML file
+    /// [default: $XDG_CONFIG_HOME/statspi/config.toml, if it exists]
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Write the received signals to FILE
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Replay the signals from FILE instead of listening to the bus
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Replay speed, relative to the recording
+    #[arg(long, default_value_t = 1.0, value_parser = positive_float, requires = "replay")]
+    pub speed: f64,
+
+    /// Count only the events that pass EXPR, a comma separated list of
+    /// `field=value` and `field!=value` clauses. The fields are category, member,
+    /// sender (bus name), app (accessible name), path (prefix) and detail.
+    /// E.g. "category=object,member=StateChanged,detail!=showing"
+    #[arg(long, value_name = "EXPR")]
+    pub filter: Option<Filter>,
+
+    /// Print snapshots to stdout instead of running the dashboard
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Seconds between snapshots in headless mode, at most one announcement
+    /// per interval with `--format speech`
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = 5,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub interval: u64,
+
+    /// Format of the snapshots in headless mode
+    #[arg(long, value_enum, default_value_t, requires = "headless")]
+    pub format: OutputFormat,
+
+    /// Arrangement of the dashboard panes
+    #[arg(long, value_enum, default_value_t)]
+    pub layout: Preset,
+
+    /// Write session reports to DIR [default: $XDG_STATE_HOME/statspi]
+    #[arg(long, value_name = "DIR")]
+    pub report_dir: Option<PathBuf>,
+
+    /// Do not write a session report on exit
+    #[arg(long)]
+    pub no_report: bool,
+
+    /// Serve metrics for Prometheus on ADDR, e.g. 127.0.0.1:9477
+    #[cfg(feature = "prometheus")]
+    #[arg(long, value_name = "ADDR")]
+    pub metrics: Option<std::net::SocketAddr>,
+
+    #[command(flatten)]
+    pub tunables: Tunables,
+}
+
+fn positive_float(s: &str) -> std::result::Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(value),
+        Ok(_) => Err("must be a positive number".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Sampling tunables. Each can be set in the config file and overridden on the command line.
+#[derive(Debug, Clone, Default, Args, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tunables {
+    /// Milliseconds per tick of the signal monitor [default: 100]
+    #[arg(long, value_name = "MS")]
+    pub tick_ms: Option<u64>,
+
+    /// Number of ticks the signal monitor keeps [default: 200]
+    #[arg(long, value_name = "TICKS")]
+    pub tick_history: Option<usize>,
+
+    /// Seconds of events per category the traffic mix chart keeps [default: 300]
+    #[arg(long, value_name = "SECS")]
+    pub mix_history: Option<usize>,
+
+    /// Seconds the history keeps by the second, at least 900 [default: 1800]
+    #[arg(long, value_name = "SECS")]
+    pub secs_capacity: Option<usize>,
+
+    /// Minutes the history keeps by the minute [default: 1440]
+    #[arg(long, value_name = "MINUTES")]
+    pub minutes_capacity: Option<usize>,
+
+    /// Hours the history keeps by the hour [default: 720]
+    #[arg(long, value_name = "HOURS")]
+    pub hours_capacity: Option<usize>,
+
+    /// Number of events the event log keeps [default: 2000]
+    #[arg(long, value_name = "EVENTS")]
+    pub event_log_capacity: Option<usize>,
+
+    /// Number of distinct errors kept, by kind and message [default: 100]
+    #[arg(long, value_name = "ERRORS")]
+    pub error_capacity: Option<usize>,
+
+    /// Milliseconds between pinging rounds of the bus servers [default: 2000]
+    #[arg(long, value_name = "MS")]
+    pub ping_interval_ms: Option<u64>,
+
+    /// Milliseconds between pinging two bus servers [default: 20]
+    #[arg(long, value_name = "MS")]
+    pub ping_gap_ms: Option<u64>,
+
+    /// Milliseconds a bus server gets to respond to a ping [default: 50]
+    #[arg(long, value_name = "MS")]
+    pub rtt_deadline_ms: Option<u64>,
+
+    /// Calls per second the tree crawler makes, at most 1000, 0 turns it off [default: 100]
+    #[arg(long, value_name = "CALLS")]
+    pub crawl_rate: Option<u32>,
+
+    /// Seconds between crawls of the accessible trees [default: 300]
+    #[arg(long, value_name = "SECS")]
+    pub crawl_interval_secs: Option<u64>,
+
+    /// Alert when the event rate is over RATE per second, 0 turns it off [default: 0]
+    #[arg(long, value_name = "RATE")]
+    pub alert_rate: Option<u64>,
+
+    /// Seconds in a row a rule has to hold before it alerts [default: 3]
+    #[arg(long, value_name = "SECS")]
+    pub alert_secs: Option<u64>,
+
+    /// Alert when the event rate is FACTOR times its recent mean, 0 turns it off [default: 5]
+    #[arg(long, value_name = "FACTOR")]
+    pub alert_factor: Option<f64>,
+
+    /// Alert when one application sends over PERCENT of the events, 0 turns it off [default: 90]
+    #[arg(long, value_name = "PERCENT")]
+    pub alert_share: Option<f64>,
+
+    /// Event rate below which the factor and share rules do not alert [default: 50]
+    #[arg(long, value_name = "RATE")]
+    pub alert_min_rate: Option<u64>,
+
+    /// Shell command run when an alert is raised, with STATSPI_ALERT_RULE,
+    /// STATSPI_ALERT_MESSAGE and STATSPI_ALERT_RATE set
+    #[arg(long, value_name = "COMMAND")]
+    pub alert_hook: Option<String>,
+}
+
+impl Tunables {
+    /// Tunables set in `self`, completed with those set in `other`.
+    fn or(self, other: Tunables) -> Tunables {
+        Tunables {
+            tick_ms: self.tick_ms.or(other.tick_ms),
+            tick_history: self.tick_history.or(other.tick_history),
+            mix_history: self.mix_history.or(other.mix_history),
+            secs_capacity: self.secs_capacity.or(other.secs_capacity),
+            minutes_capacity: self.minutes_capacity.or(other.minutes_capacity),
+            hours_capacity: self.hours_capacity.or(other.hours_capacity),
+            event_log_capacity: self.event_log_capacity.or(other.event_log_capacity),
+            error_capacity: self.error_capacity.or(other.error_capacity),
+            ping_interval_ms: self.ping_interval_ms.or(other.ping_interval_ms),
+            ping_gap_ms: self.ping_gap_ms.or(other.ping_gap_ms),
+            rtt_deadline_ms: self.rtt_deadline_ms.or(other.rtt_deadline_ms),
+            crawl_rate: self.crawl_rate.or(other.crawl_rate),
+            crawl_interval_secs: self.crawl_interval_secs.or(other.crawl_interval_secs),
+            alert_rate: self.alert_rate.or(other.alert_rate),
+            alert_secs: self.alert_secs.or(other.alert_secs),
+            alert_factor: self.alert_factor.or(other.alert_factor),
+            alert_share: self.alert_share.or(other.alert_share),
+            alert_min_rate: self.alert_min_rate.or(other.alert_min_rate),
+            alert_hook: self.alert_hook.or(other.alert_hook),
+        }
+    }
+}
+
+/// The tunables in effect.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub tick: Duration,
+    pub tick_history: usize,
+    pub mix_history: usize,
+    pub secs_capacity: usize,
+    pub minutes_capacity: usize,
+    pub hours_capacity: usize,
+    pub event_log_capacity: usize,
+    pub error_capacity: usize,
+    pub ping_interval: Duration,
+    pub ping_gap: Duration,
+    pub rtt_deadline: Duration,
+    pub crawl_rate: u32,
+    pub crawl_interval: Duration,
+    pub alert_rate: u64,
+    pub alert_secs: u64,
+    pub alert_factor: f64,
+    pub alert_share: f64,
+    pub alert_min_rate: u64,
+    pub alert_hook: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            tick: Duration::from_millis(100),
+            tick_history: 200,
+            mix_history: 300,
+            secs_capacity: 1800,    // 30 minutes
+            minutes_capacity: 1440, // a day
+            hours_capacity: 720,    // 30 days
+            event_log_capacity: 2000,
+            error_capacity: 100,
+            ping_interval: Duration::from_secs(2),
+            ping_gap: Duration::from_millis(20),
+            rtt_deadline: Duration::from_millis(50),
+            crawl_rate: 100,
+            crawl_interval: Duration::from_secs(300),
+            alert_rate: 0,
+            alert_secs: 3,
+            alert_factor: 5.0,
+            alert_share: 90.0,
+            alert_min_rate: 50,
+            alert_hook: None,
+        }
+    }
+}
+
+impl Config {
+    /// The command line overrides the config file, which overrides the defaults.
+    pub fn load(cli: &Cli) -> Result<Config> {
+        let from_file = match &cli.config {
+            Some(path) => read_tunables(path)?,
+            None => match default_config_path() {
+                Some(path) if path.exists() => read_tunables(&path)?,
+                _ => Tunables::default(),
+            },
+        };
+
+        let tunables = cli.tunables.clone().or(from_file);
+        let defaults = Config::default();
+        let ms =
+            |value: Option<u64>, default: Duration| value.map_or(default, Duration::from_millis);
+
+        let config = Config {
+            tick: ms(tunables.tick_ms, defaults.tick),
+            tick_history: tunables.tick_history.unwrap_or(defaults.tick_history),
+            mix_history: tunables.mix_history.unwrap_or(defaults.mix_history),
+            secs_capacity: tunables.secs_capacity.unwrap_or(defaults.secs_capacity),
+            minutes_capacity: tunables
+                .minutes_capacity
+                .unwrap_or(defaults.minutes_capacity),
+            hours_capacity: tunables.hours_capacity.unwrap_or(defaults.hours_capacity),
+            event_log_capacity: tunables
+                .event_log_capacity
+                .unwrap_or(defaults.event_log_capacity),
+            error_capacity: tunables.error_capacity.unwrap_or(defaults.error_capacity),
+            ping_interval: ms(tunables.ping_interval_ms, defaults.ping_interval),
+            ping_gap: ms(tunables.ping_gap_ms, defaults.ping_gap),
+            rtt_deadline: ms(tunables.rtt_deadline_ms, defaults.rtt_deadline),
+            crawl_rate: tunables.crawl_rate.unwrap_or(defaults.crawl_rate),
+            crawl_interval: tunables
+                .crawl_interval_secs
+                .map_or(defaults.crawl_interval, Duration::from_secs),
+            alert_rate: tunables.alert_rate.unwrap_or(defaults.alert_rate),
+            alert_secs: tunables.alert_secs.unwrap_or(defaults.alert_secs),
+            alert_factor: tunables.alert_factor.unwrap_or(defaults.alert_factor),
+            alert_share: tunables.alert_share.unwrap_or(defaults.alert_share),
+            alert_min_rate: tunables.alert_min_rate.unwrap_or(defaults.alert_min_rate),
+            alert_hook: tunables.alert_hook.or(defaults.alert_hook),
+        };
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !(10..=10_000).contains(&self.tick.as_millis()) {
+            return Err("tick-ms must be between 10 and 10000".into());
+        }
+        if self.tick_history == 0 {
+            return Err("tick-history must be at least 1".into());
+        }
+        if self.mix_history == 0 {
+            return Err("mix-history must be at least 1".into());
+        }
+        // The rolling stats look back 15 minutes.
+        if self.secs_capacity < 900 {
+            return Err("secs-capacity must be at least 900".into());
+        }
+        if self.minutes_capacity == 0 || self.hours_capacity == 0 {
+            return Err("minutes-capacity and hours-capacity must be at least 1".into());
+        }
+        if self.event_log_capacity == 0 {
+            return Err("event-log-capacity must be at least 1".into());
+        }
+        if self.error_capacity == 0 {
+            return Err("error-capacity must be at least 1".into());
+        }
+        if self.ping_interval.is_zero() {
+            return Err("ping-interval-ms must be at least 1".into());
+        }
+        if self.ping_gap.is_zero() {
+            return Err("ping-gap-ms must be at least 1".into());
+        }
+        if self.rtt_deadline.is_zero() {
+            return Err("rtt-deadline-ms must be at least 1".into());
+        }
+        if self.rtt_deadline >= self.ping_interval {
+            return Err("rtt-deadline-ms must be shorter than ping-interval-ms".into());
+        }
+        // The crawler is meant to stay light.
+        if self.crawl_rate > 1000 {
+            return Err("crawl-rate must be at most 1000".into());
+        }
+        if self.crawl_interval.is_zero() {
+            return Err("crawl-interval-secs must be at least 1".into());
+        }
+        if self.alert_secs == 0 {
+            return Err("alert-secs must be at least 1".into());
+        }
+        if !(self.alert_factor >= 0.0 && self.alert_factor.is_finite()) {
+            return Err("alert-factor must be a positive number, or 0".into());
+        }
+        if !(0.0..=100.0).contains(&self.alert_share) {
+            return Err("alert-share must be a percentage".into());
+        }
+
+        Ok(())
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+    Some(config_home.join("statspi").join("config.toml"))
+}
+
+fn read_tunables(path: &Path) -> Result<Tunables> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read config file {}: {e}", path.display()))?;
+
+    toml::from_str(&text).map_err(|e| format!("invalid config file {}: {e}", path.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A config file with `text`, named after the test writing it.
+    fn config_file(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("statspi-{}-{name}.toml", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn load(file: &Path, args: &[&str]) -> Result<Config> {
+        let config = ["statspi", "--config", file.to_str().unwrap()];
+        let cli = Cli::try_parse_from(config.iter().chain(args))?;
+        Config::load(&cli)
+    }
+
+    #[test]
+    fn the_command_line_overrides_the_file() {
+        let file = config_file(
+            "overrides",
+            "tick_ms = 300\nping_gap_ms = 5\nalert_hook = \"notify-send storm\"\n",
+        );
+        let config = load(&file, &["--tick-ms", "50", "--crawl-rate", "0"]).unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        // Set on the command line.
+        assert_eq!(config.tick, Duration::from_millis(50));
+        assert_eq!(config.crawl_rate, 0);
+        // Set in the file only.
+        assert_eq!(config.ping_gap, Duration::from_millis(5));
+        assert_eq!(config.alert_hook.as_deref(), Some("notify-send storm"));
+        // Set nowhere.
+        assert_eq!(config.ping_interval, Config::default().ping_interval);
+        assert_eq!(config.secs_capacity, Config::default().secs_capacity);
+    }
+
+    #[test]
+    fn bad_config_files() {
+        let file = config_file("unknown", "tick = 300\n");
+        let error = load(&file, &[]).unwrap_err().to_string();
+        std::fs::remove_file(file).unwrap();
+        assert!(error.starts_with("invalid config file"), "{error}");
+
+        // The file is held to the same ranges, unless the command line overrides it.
+        let file = config_file("file-range", "ping_gap_ms = 0\n");
+        let error = load(&file, &[]).unwrap_err().to_string();
+        let overridden = load(&file, &["--ping-gap-ms", "10"]);
+        std::fs::remove_file(file).unwrap();
+        assert_eq!(error, "ping-gap-ms must be at least 1");
+        assert!(overridden.is_ok());
+    }
+
+    #[test]
+    fn out_of_range_tunables() {
+        let file = config_file("range", "");
+        for (arg, error) in [
+            ("--tick-ms=5", "tick-ms must be between 10 and 10000"),
+            ("--tick-ms=10001", "tick-ms must be between 10 and 10000"),
+            ("--tick-history=0", "tick-history must be at least 1"),
+            ("--mix-history=0", "mix-history must be at least 1"),
+            ("--secs-capacity=899", "secs-capacity must be at least 900"),
+            (
+                "--minutes-capacity=0",
+                "minutes-capacity and hours-capacity must be at least 1",
+            ),
+            (
+                "--hours-capacity=0",
+                "minutes-capacity and hours-capacity must be at least 1",
+            ),
+            (
+                "--event-log-capacity=0",
+                "event-log-capacity must be at least 1",
+            ),
+            ("--error-capacity=0", "error-capacity must be at least 1"),
+            (
+                "--ping-interval-ms=0",
+                "ping-interval-ms must be at least 1",
+            ),
+            ("--ping-gap-ms=0", "ping-gap-ms must be at least 1"),
+            ("--rtt-deadline-ms=0", "rtt-deadline-ms must be at least 1"),
+            (
+                "--rtt-deadline-ms=2000",
+                "rtt-deadline-ms must be shorter than ping-interval-ms",
+            ),
+            ("--crawl-rate=1001", "crawl-rate must be at most 1000"),
+            (
+                "--crawl-interval-secs=0",
+                "crawl-interval-secs must be at least 1",
+            ),
+            ("--alert-secs=0", "alert-secs must be at least 1"),
+            (
+                "--alert-factor=-1",
+                "alert-factor must be a positive number, or 0",
+            ),
+            (
+                "--alert-factor=inf",
+                "alert-factor must be a positive number, or 0",
+            ),
+            ("--alert-share=101", "alert-share must be a percentage"),
+        ] {
+            let result = load(&file, &[arg]);
+            assert_eq!(result.unwrap_err().to_string(), error, "{arg}");
+        }
+
+        // The edges of the ranges are fine.
+        let edges = [
+            "--tick-ms=10",
+            "--secs-capacity=900",
+            "--crawl-rate=1000",
+            "--alert-factor=0",
+            "--alert-share=100",
+        ];
+        let result = load(&file, &edges);
+        std::fs::remove_file(file).unwrap();
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::{category::Category, App};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+
+/// A named point on the timeline, e.g. "dialog opened".
+#[derive(Debug, Clone, Serialize)]
+pub struct Marker {
+    /// Seconds since statspi started counting.
+    pub second: usize,
+    pub name: String,
+}
+
+/// The counters a reset zeroes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetScope {
+    /// Every counter: the categories, the members, the top talkers and the rates.
+    All,
+    /// The counters of one category.
+    Category(Category),
+}
+
+/// Take commands from other processes: SIGUSR1 places a marker, SIGUSR2 resets the counters.
+/// This lets a test script mark the steps it takes, e.g. `pkill -USR1 statspi`.
+pub async fn listen_signals(app: Arc<App>) {
+    let (mut mark, mut reset) = match (
+        signal(SignalKind::user_defined1()),
+        signal(SignalKind::user_defined2()),
+    ) {
+        (Ok(mark), Ok(reset)) => (mark, reset),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!("Cannot listen for control signals: {e}");
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            Some(()) = mark.recv() => app.mark("SIGUSR1"),
+            Some(()) = reset.recv() => app.reset(ResetScope::All),
+            else => return,
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{
+    bus::Server,
+    tree::{accessible, within},
+    App, ACCESSIBLE_ROOT_PATH,
+};
+use atspi::ObjectRef;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fmt::Formatter,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::Mutex as AsyncMutex,
+    time::{Interval, MissedTickBehavior},
+};
+use zbus::Connection;
+
+// A crawl stops here, so a runaway tree cannot keep the crawler forever.
+const MAX_NODES: u64 = 200_000;
+
+// Nodes crawled between publishing the stats so far.
+const PUBLISH_EVERY: u64 = 250;
+
+/// The size and shape of an application's accessible tree.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TreeStats {
+    pub nodes: u64,
+    /// Depth of the deepest node, the root is at depth 0.
+    pub max_depth: usize,
+    /// Most children of a single node.
+    pub max_fan_out: usize,
+    /// Nodes per role.
+    pub roles: BTreeMap<String, u64>,
+    /// Nodes that did not answer.
+    pub errors: u64,
+    /// The crawl is still going.
+    pub crawling: bool,
+    /// The crawl stopped at the node limit.
+    pub truncated: bool,
+    #[serde(skip)]
+    pub finished: Option<Instant>,
+}
+
+impl TreeStats {
+    fn add(&mut self, role: String, depth: usize, children: usize) {
+        self.nodes += 1;
+        self.max_depth = self.max_depth.max(depth);
+        self.max_fan_out = self.max_fan_out.max(children);
+        *self.roles.entry(role).or_default() += 1;
+    }
+}
+
+// Pretty print the tree stats, with the most common roles.
+impl std::fmt::Display for TreeStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "tree: {}{} nodes depth: {} fan-out: {}",
+            self.nodes,
+            if self.truncated { "+" } else { "" },
+            self.max_depth,
+            self.max_fan_out
+        )?;
+        if self.errors > 0 {
+            write!(f, " unanswered: {}", self.errors)?;
+        }
+        match self.finished {
+            _ if self.crawling => write!(f, " (crawling)")?,
+            Some(at) => write!(f, " ({}s ago)", at.elapsed().as_secs())?,
+            None => {}
+        }
+
+        let mut roles: Vec<_> = self.roles.iter().collect();
+        roles.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+        for (role, count) in roles.into_iter().take(3) {
+            write!(f, " {role}: {count}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Crawl the trees of the servers on the bus, one after the other, every `interval`.
+/// The crawler makes at most `rate` calls per second, so it does not add much load.
+pub async fn run(app: Arc<App>, rate: u32, interval: Duration) {
+    let mut pace = tokio::time::interval(Duration::from_secs(1) / rate);
+    pace.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        for server in app.servers.live() {
+            crawl(&server, &mut pace).await;
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Walk the tree of `server` breadth first, publishing the stats on the server as they grow.
+async fn crawl(server: &AsyncMutex<Server>, pace: &mut Interval) {
+    let (conn, bus_name): (Connection, String) = {
+        let guard = server.lock().await;
+        if guard.departed.is_some() {
+            return;
+        }
+        (
+            guard.accessible_proxy.connection().clone(),
+            guard.bus_name.to_string(),
+        )
+    };
+
+    let root = ObjectRef {
+        name: bus_name.clone(),
+        path: ACCESSIBLE_ROOT_PATH.into(),
+    };
+    let mut seen = HashSet::from([root.path.clone()]);
+    let mut queue = VecDeque::from([(root, 0)]);
+    let mut stats = TreeStats {
+        crawling: true,
+        ..TreeStats::default()
+    };
+
+    while let Some((object, depth)) = queue.pop_front() {
+        if stats.nodes >= MAX_NODES {
+            stats.truncated = true;
+            break;
+        }
+
+        pace.tick().await;
+        let Ok(proxy) = accessible(&conn, &object).await else {
+            stats.errors += 1;
+            continue;
+        };
+        let role = match within(proxy.get_role()).await {
+            Ok(role) => role.name().to_string(),
+            Err(_) => {
+                stats.errors += 1;
+                continue;
+            }
+        };
+
+        pace.tick().await;
+        let children = within(proxy.get_children()).await.unwrap_or_else(|_| {
+            stats.errors += 1;
+            Vec::new()
+        });
+
+        stats.add(role, depth, children.len());
+        // Objects embedded from other applications belong to their trees.
+        queue.extend(
+            children
+                .into_iter()
+                .filter(|child| child.name == bus_name)
+                .filter(|child| seen.insert(child.path.clone()))
+                .map(|child| (child, depth + 1)),
+        );
+
+        // A recrawl keeps the stats of the last crawl up until it is done.
+        if stats.nodes % PUBLISH_EVERY == 0 {
+            let mut guard = server.lock().await;
+            if guard.tree.as_ref().is_none_or(|tree| tree.crawling) {
+                guard.tree = Some(stats.clone());
+            }
+        }
+    }
+
+    stats.crawling = false;
+    stats.finished = Some(Instant::now());
+    server.lock().await.tree = Some(stats);
+}
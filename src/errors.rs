@@ -0,0 +1,228 @@
+use crate::{bus::error_name, signal::SignalHeader};
+use atspi::AtspiError;
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fmt::Formatter,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// What went wrong with a signal.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The signal is not an AT-SPI event statspi knows.
+    UnknownSignal,
+    /// The body of the signal does not have the signature of its event.
+    Signature,
+    /// The signal has the right shape, but a part of it could not be read.
+    Parse,
+    /// The bus failed to deliver the message, by D-Bus error name.
+    Bus(String),
+    Other,
+}
+
+impl ErrorKind {
+    /// Classify an error that came with a signal.
+    pub fn of(error: &(dyn Error + 'static)) -> ErrorKind {
+        if let Some(replayed) = error.downcast_ref::<ReplayedError>() {
+            return replayed.kind.clone();
+        }
+        if let Some(e) = error.downcast_ref::<zbus::Error>() {
+            return ErrorKind::Bus(error_name(e));
+        }
+
+        match error.downcast_ref::<AtspiError>() {
+            Some(
+                AtspiError::UnknownSignal
+                | AtspiError::UnknownInterface
+                | AtspiError::MemberMatch(_)
+                | AtspiError::InterfaceMatch(_)
+                | AtspiError::CacheVariantMismatch,
+            ) => ErrorKind::UnknownSignal,
+            Some(AtspiError::UnknownBusSignature(_)) => ErrorKind::Signature,
+            Some(
+                AtspiError::Conversion(_)
+                | AtspiError::ParseError(_)
+                | AtspiError::Zvariant(_)
+                | AtspiError::ZBusNames(_)
+                | AtspiError::PathConversionError(_)
+                | AtspiError::IntConversionError(_)
+                | AtspiError::UnknownRole(_)
+                | AtspiError::MissingInterface
+                | AtspiError::MissingMember
+                | AtspiError::MissingName,
+            ) => ErrorKind::Parse,
+            // The zbus error comes as its debug output, the variant name first.
+            Some(AtspiError::Zbus(e)) => {
+                ErrorKind::Bus(e.split(['(', ' ']).next().unwrap_or(e).to_string())
+            }
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::UnknownSignal => write!(f, "unknown signal"),
+            ErrorKind::Signature => write!(f, "signature"),
+            ErrorKind::Parse => write!(f, "parse"),
+            ErrorKind::Bus(name) => write!(f, "bus {name}"),
+            ErrorKind::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// An error read back from a recording, which keeps its kind but not its type.
+#[derive(Debug)]
+pub struct ReplayedError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl std::fmt::Display for ReplayedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ReplayedError {}
+
+/// The errors of one kind with the same message.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorEntry {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub count: u64,
+    /// Time since statspi started, in seconds in a report.
+    #[serde(serialize_with = "as_secs")]
+    pub first_seen: Duration,
+    #[serde(serialize_with = "as_secs")]
+    pub last_seen: Duration,
+    /// The header of the last signal that had the error, if it had one.
+    pub header: Option<SignalHeader>,
+}
+
+fn as_secs<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Order of the error list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorSort {
+    #[default]
+    Count,
+    Recent,
+    Kind,
+}
+
+impl ErrorSort {
+    pub fn next(self) -> ErrorSort {
+        match self {
+            ErrorSort::Count => ErrorSort::Recent,
+            ErrorSort::Recent => ErrorSort::Kind,
+            ErrorSort::Kind => ErrorSort::Count,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorSort::Count => "count",
+            ErrorSort::Recent => "last seen",
+            ErrorSort::Kind => "kind",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct StoreState {
+    entries: Vec<ErrorEntry>,
+    // Entries that made room for new ones.
+    evicted: u64,
+}
+
+/// The errors met, grouped by kind and message, up to a fixed number of groups.
+/// A new group pushes out the one seen longest ago.
+#[derive(Debug)]
+pub struct ErrorStore {
+    started: Instant,
+    capacity: usize,
+    state: Mutex<StoreState>,
+}
+
+impl Clone for ErrorStore {
+    fn clone(&self) -> Self {
+        ErrorStore {
+            started: self.started,
+            capacity: self.capacity,
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl ErrorStore {
+    pub fn new(capacity: usize) -> ErrorStore {
+        ErrorStore {
+            started: Instant::now(),
+            capacity,
+            state: Mutex::new(StoreState::default()),
+        }
+    }
+
+    pub fn add(&self, kind: ErrorKind, message: String, header: Option<&SignalHeader>) {
+        let now = self.started.elapsed();
+        let mut state = self.state.lock().unwrap();
+
+        let found = state
+            .entries
+            .iter_mut()
+            .find(|entry| entry.kind == kind && entry.message == message);
+        if let Some(entry) = found {
+            entry.count += 1;
+            entry.last_seen = now;
+            entry.header = header.cloned();
+            return;
+        }
+
+        if state.entries.len() == self.capacity {
+            let oldest = state
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.last_seen)
+                .map(|(i, _)| i);
+            if let Some(i) = oldest {
+                state.entries.swap_remove(i);
+                state.evicted += 1;
+            }
+        }
+        state.entries.push(ErrorEntry {
+            kind,
+            message,
+            count: 1,
+            first_seen: now,
+            last_seen: now,
+            header: header.cloned(),
+        });
+    }
+
+    /// The entries kept, in the order asked for.
+    pub fn sorted(&self, sort: ErrorSort) -> Vec<ErrorEntry> {
+        let mut entries = self.state.lock().unwrap().entries.clone();
+        match sort {
+            ErrorSort::Count => entries.sort_by_key(|entry| std::cmp::Reverse(entry.count)),
+            ErrorSort::Recent => entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_seen)),
+            ErrorSort::Kind => {
+                entries.sort_by(|a, b| (&a.kind, &a.message).cmp(&(&b.kind, &b.message)))
+            }
+        }
+        entries
+    }
+
+    /// Groups pushed out to make room for new ones.
+    pub fn evicted(&self) -> u64 {
+        self.state.lock().unwrap().evicted
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{category::Category, signal::SignalHeader};
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// An event as it is shown in the event log.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// Numbers the events logged, so a position in the log survives older entries falling off.
+    pub seq: u64,
+    /// Time since statspi started.
+    pub at: Duration,
+    pub category: Category,
+    /// Accessible name of the sender, or its bus name if it is not a known server.
+    pub app: String,
+    pub interface: String,
+    pub member: String,
+    pub path: String,
+    pub detail: String,
+    /// Why the event could not be read, if it could not.
+    pub error: Option<String>,
+}
+
+impl LogEntry {
+    /// The entry as a line of text, this is also what a search looks in.
+    pub fn line(&self) -> String {
+        let interface = self
+            .interface
+            .strip_prefix("org.a11y.atspi.")
+            .unwrap_or(&self.interface);
+
+        match &self.error {
+            Some(error) => format!(
+                "{:>10.3}s {:<16} error: {error}",
+                self.at.as_secs_f64(),
+                self.app
+            ),
+            None => format!(
+                "{:>10.3}s {:<16} {interface}.{} {} {}",
+                self.at.as_secs_f64(),
+                self.app,
+                self.member,
+                self.path,
+                self.detail
+            ),
+        }
+    }
+}
+
+/// The most recent events, oldest first, in a ring buffer of fixed capacity.
+#[derive(Debug)]
+pub struct EventLog {
+    started: Instant,
+    capacity: usize,
+    entries: Mutex<VecDeque<LogEntry>>,
+}
+
+impl Clone for EventLog {
+    fn clone(&self) -> Self {
+        EventLog {
+            started: self.started,
+            capacity: self.capacity,
+            entries: Mutex::new(self.entries.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> EventLog {
+        EventLog {
+            started: Instant::now(),
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn push(
+        &self,
+        category: Category,
+        app: String,
+        header: Option<&SignalHeader>,
+        error: Option<String>,
+    ) {
+        let field = |get: fn(&SignalHeader) -> &Option<String>| {
+            header.and_then(|h| get(h).clone()).unwrap_or_default()
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        let seq = entries.back().map_or(0, |last| last.seq + 1);
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+
+        entries.push_back(LogEntry {
+            seq,
+            at: self.started.elapsed(),
+            category,
+            app,
+            interface: field(|h| &h.interface),
+            member: field(|h| &h.member),
+            path: field(|h| &h.path),
+            detail: field(|h| &h.detail),
+            error,
+        });
+    }
+
+    /// Sequence numbers of the oldest and newest entries, `None` if nothing was logged yet.
+    pub fn bounds(&self) -> Option<(u64, u64)> {
+        let entries = self.entries.lock().unwrap();
+        Some((entries.front()?.seq, entries.back()?.seq))
+    }
+
+    /// Up to `rows` entries, ending with the one at `last`, or the newest if `last` is `None`.
+    pub fn window(&self, last: Option<u64>, rows: usize) -> Vec<LogEntry> {
+        let entries = self.entries.lock().unwrap();
+        let Some(oldest) = entries.front().map(|first| first.seq) else {
+            return Vec::new();
+        };
+
+        // Entries that fell off while the log was looked at are replaced by the oldest ones.
+        let end = match last {
+            Some(seq) => (seq.saturating_sub(oldest) as usize + 1).max(rows),
+            None => entries.len(),
+        }
+        .min(entries.len());
+        entries
+            .range(end.saturating_sub(rows)..end)
+            .cloned()
+            .collect()
+    }
+
+    /// The entry nearest to `from` whose line contains `query`, ignoring case.
+    /// Looks at `from` itself and older entries, or newer ones if `newer` is set.
+    pub fn find(&self, query: &str, from: u64, newer: bool) -> Option<u64> {
+        let query = query.to_lowercase();
+        let matches = |entry: &&LogEntry| entry.line().to_lowercase().contains(&query);
+
+        let entries = self.entries.lock().unwrap();
+        let found = if newer {
+            entries.iter().filter(|e| e.seq >= from).find(matches)
+        } else {
+            entries.iter().rev().filter(|e| e.seq <= from).find(matches)
+        };
+        found.map(|entry| entry.seq)
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{bus::Servers, category::Category, signal::SignalHeader};
+use std::{fmt::Formatter, str::FromStr};
+
+/// The parts of an event a filter clause can look at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Category,
+    Member,
+    Sender,
+    App,
+    Path,
+    Detail,
+}
+
+impl Field {
+    const COUNT: usize = 6;
+
+    fn name(&self) -> &'static str {
+        match self {
+            Field::Category => "category",
+            Field::Member => "member",
+            Field::Sender => "sender",
+            Field::App => "app",
+            Field::Path => "path",
+            Field::Detail => "detail",
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "category" => Ok(Field::Category),
+            "member" => Ok(Field::Member),
+            "sender" => Ok(Field::Sender),
+            "app" => Ok(Field::App),
+            "path" => Ok(Field::Path),
+            "detail" => Ok(Field::Detail),
+            _ => Err(format!(
+                "unknown field '{s}', expected category, member, sender, app, path or detail"
+            )),
+        }
+    }
+}
+
+/// One `field=value` or `field!=value` test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Clause {
+    field: Field,
+    negated: bool,
+    value: String,
+    // Parsed from `value` for category clauses.
+    category: Option<Category>,
+}
+
+impl Clause {
+    fn matches(
+        &self,
+        category: Category,
+        header: Option<&SignalHeader>,
+        servers: &Servers,
+    ) -> bool {
+        // Events that could not be received have no header to look at.
+        let sender = header.and_then(|header| header.sender.as_deref());
+        let value = Some(self.value.as_str());
+
+        match self.field {
+            Field::Category => self.category == Some(category),
+            Field::Member => header.and_then(|header| header.member.as_deref()) == value,
+            Field::Sender => sender == value,
+            Field::App => sender
+                .and_then(|sender| servers.accessible_name(sender))
+                .is_some_and(|name| name.eq_ignore_ascii_case(&self.value)),
+            Field::Path => header
+                .and_then(|header| header.path.as_deref())
+                .is_some_and(|path| path.starts_with(&self.value)),
+            Field::Detail => header.and_then(|header| header.detail.as_deref()) == value,
+        }
+    }
+}
+
+impl FromStr for Clause {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, negated, value) = match s.split_once("!=") {
+            Some((field, value)) => (field, true, value),
+            None => match s.split_once('=') {
+                Some((field, value)) => (field, false, value),
+                None => return Err(format!("'{s}' is not of the form field=value")),
+            },
+        };
+
+        let field: Field = field.trim().parse()?;
+        let value = value.trim().to_string();
+        if value.is_empty() {
+            return Err(format!("{} needs a value", field.name()));
+        }
+
+        let category = match field {
+            Field::Category => Some(
+                Category::ALL
+                    .into_iter()
+                    .find(|category| category.name().eq_ignore_ascii_case(&value))
+                    .ok_or_else(|| format!("unknown category '{value}'"))?,
+            ),
+            _ => None,
+        };
+
+        Ok(Clause {
+            field,
+            negated,
+            value,
+            category,
+        })
+    }
+}
+
+/// Which events are counted, e.g. `category=object,member=StateChanged,detail!=showing`.
+///
+/// An event passes if, for each field with `=` clauses, it matches one of them,
+/// and it matches none of the `!=` clauses. Paths match by prefix.
+/// The empty filter passes every event.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    clauses: Vec<Clause>,
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    pub fn matches(
+        &self,
+        category: Category,
+        header: Option<&SignalHeader>,
+        servers: &Servers,
+    ) -> bool {
+        let mut wanted = [false; Field::COUNT];
+        let mut met = [false; Field::COUNT];
+
+        for clause in self.clauses.iter() {
+            let hit = clause.matches(category, header, servers);
+            if clause.negated {
+                if hit {
+                    return false;
+                }
+            } else {
+                wanted[clause.field as usize] = true;
+                met[clause.field as usize] |= hit;
+            }
+        }
+
+        wanted == met
+    }
+
+    /// Whether events of `category` can pass at all, going by the category clauses alone.
+    /// Event families no category can pass for need not be registered for.
+    pub fn admits(&self, category: Category) -> bool {
+        let mut included = None;
+        for clause in self.clauses.iter().filter(|c| c.field == Field::Category) {
+            let hit = clause.category == Some(category);
+            if clause.negated {
+                if hit {
+                    return false;
+                }
+            } else {
+                included = Some(included.unwrap_or(false) || hit);
+            }
+        }
+
+        included.unwrap_or(true)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let clauses = s
+            .split(',')
+            .map(str::trim)
+            .filter(|clause| !clause.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Filter { clauses })
+    }
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            let op = if clause.negated { "!=" } else { "=" };
+            write!(f, "{}{op}{}", clause.field.name(), clause.value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(sender: &str, path: &str, member: &str, detail: &str) -> SignalHeader {
+        SignalHeader {
+            sender: Some(sender.to_string()),
+            interface: None,
+            path: Some(path.to_string()),
+            member: Some(member.to_string()),
+            detail: Some(detail.to_string()),
+        }
+    }
+
+    fn servers() -> Servers {
+        Servers::replayed(&[
+            (":1.5".to_string(), "gedit".to_string()),
+            (":1.7".to_string(), "Firefox".to_string()),
+        ])
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("category".parse::<Filter>().is_err());
+        assert!("colour=red".parse::<Filter>().is_err());
+        assert!("member=".parse::<Filter>().is_err());
+        assert!("category=nonsense".parse::<Filter>().is_err());
+        assert!("app=gedit,detail".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn parse_negation_before_equality() {
+        let filter: Filter = " detail!=showing , category=object,,".parse().unwrap();
+        assert_eq!(filter.to_string(), "detail!=showing,category=object");
+        assert_eq!(filter.clauses[0].field, Field::Detail);
+        assert!(filter.clauses[0].negated);
+        assert_eq!(filter.clauses[0].value, "showing");
+        assert_eq!(filter.clauses[1].category, Some(Category::Object));
+
+        assert!("".parse::<Filter>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn alternatives_within_a_field_all_fields_across() {
+        let filter: Filter = "app=gedit,app=firefox,member=StateChanged,detail!=showing"
+            .parse()
+            .unwrap();
+        let servers = servers();
+        let matches = |sender, member, detail| {
+            let header = header(sender, "/org/a11y/atspi/accessible/1", member, detail);
+            filter.matches(Category::Object, Some(&header), &servers)
+        };
+
+        assert!(matches(":1.5", "StateChanged", "focused"));
+        assert!(matches(":1.7", "StateChanged", "focused"));
+        // Another application.
+        assert!(!matches(":1.9", "StateChanged", "focused"));
+        // The member clause must hold too.
+        assert!(!matches(":1.5", "PropertyChange", "focused"));
+        // Excluded by the negated clause.
+        assert!(!matches(":1.5", "StateChanged", "showing"));
+    }
+
+    #[test]
+    fn paths_match_by_prefix() {
+        let filter: Filter = "path=/org/a11y/atspi/accessible/1".parse().unwrap();
+        let servers = servers();
+        let matches = |path| {
+            let header = header(":1.5", path, "StateChanged", "focused");
+            filter.matches(Category::Object, Some(&header), &servers)
+        };
+
+        assert!(matches("/org/a11y/atspi/accessible/1"));
+        assert!(matches("/org/a11y/atspi/accessible/12"));
+        assert!(!matches("/org/a11y/atspi/accessible/root"));
+    }
+
+    #[test]
+    fn events_without_a_header() {
+        let servers = servers();
+        let negated: Filter = "app!=gedit".parse().unwrap();
+        let positive: Filter = "app=gedit".parse().unwrap();
+
+        assert!(negated.matches(Category::Error, None, &servers));
+        assert!(!positive.matches(Category::Error, None, &servers));
+        assert!(Filter::default().matches(Category::Error, None, &servers));
+    }
+
+    #[test]
+    fn admits_by_category_clauses() {
+        let all = Filter::default();
+        assert!(Category::ALL.into_iter().all(|c| all.admits(c)));
+
+        let except_mouse: Filter = "category!=mouse".parse().unwrap();
+        assert!(!except_mouse.admits(Category::Mouse));
+        assert!(except_mouse.admits(Category::Object));
+
+        let two: Filter = "category=object,category=focus".parse().unwrap();
+        assert!(two.admits(Category::Object));
+        assert!(two.admits(Category::Focus));
+        assert!(!two.admits(Category::Mouse));
+
+        // Other fields do not keep a category from being registered for.
+        let by_app: Filter = "app=gedit,member!=StateChanged".parse().unwrap();
+        assert!(Category::ALL.into_iter().all(|c| by_app.admits(c)));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{
+    alert::Alert,
+    bus::{ResponseStats, PERCENTILES},
+    category::Category,
+    crawler::TreeStats,
+    speech::Announcer,
+    App, Result, Window,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Write, sync::Arc, time::Duration};
+use tokio::signal::unix::{signal, SignalKind};
+
+/// How snapshots are printed in headless mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+    /// Short sentences for a screen reader, only what changed since the last ones.
+    Speech,
+}
+
+/// Response times of a bus server, in microseconds.
+#[derive(Debug, Serialize)]
+pub struct ServerSnapshot {
+    pub name: String,
+    pub bus_name: String,
+    pub samples: u32,
+    pub sum_us: u128,
+    pub min_us: Option<u128>,
+    pub max_us: Option<u128>,
+    pub mean_us: Option<u128>,
+    pub std_dev_us: Option<u128>,
+    /// Response time percentiles, e.g. ("p99", 1234).
+    pub percentiles_us: Vec<(&'static str, Option<u128>)>,
+    pub timeouts: u32,
+    /// Failed pings by D-Bus error name.
+    pub errors: BTreeMap<String, u32>,
+    /// Percentage of pings answered.
+    pub availability: Option<f64>,
+    pub last_responsive_secs_ago: Option<u64>,
+    /// The server has left the bus.
+    pub departed: bool,
+    /// The size of the server's accessible tree, if it was crawled.
+    pub tree: Option<TreeStats>,
+}
+
+impl ServerSnapshot {
+    fn new(
+        name: &str,
+        bus_name: &str,
+        stats: &ResponseStats,
+        departed: bool,
+        tree: Option<TreeStats>,
+    ) -> ServerSnapshot {
+        ServerSnapshot {
+            name: name.to_string(),
+            bus_name: bus_name.to_string(),
+            samples: stats.samples,
+            sum_us: stats.sum.as_micros(),
+            min_us: stats.min.map(|d| d.as_micros()),
+            max_us: stats.max.map(|d| d.as_micros()),
+            mean_us: stats.mean.map(|d| d.as_micros()),
+            std_dev_us: stats.std_dev.map(|d| d.as_micros()),
+            percentiles_us: PERCENTILES
+                .iter()
+                .map(|(label, q)| (*label, stats.percentile(*q).map(|d| d.as_micros())))
+                .collect(),
+            timeouts: stats.timeouts,
+            errors: stats.errors.clone(),
+            availability: stats.availability(),
+            last_responsive_secs_ago: stats.last_responsive.map(|at| at.elapsed().as_secs()),
+            departed,
+            tree,
+        }
+    }
+}
+
+/// The state of the scoreboard, rate stats and server response times at one moment.
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    /// Seconds since statspi started counting.
+    pub seconds: usize,
+    pub rate: u64,
+    pub peak: u64,
+    pub mean: u64,
+    pub total: u64,
+    /// Mean and peak rates over the last 1, 5 and 15 minutes.
+    pub windows: Vec<Window>,
+    /// Events the filter kept out.
+    pub filtered: u64,
+    /// Events per category, in scoreboard order.
+    pub categories: Vec<(&'static str, u64)>,
+    pub servers: Vec<ServerSnapshot>,
+    /// Alerts raised that have not ended.
+    pub alerts: Vec<Alert>,
+}
+
+impl Snapshot {
+    pub async fn take(app: &App) -> Snapshot {
+        let categories = Category::ALL
+            .iter()
+            .map(|category| (category.name(), app.tally.counter(*category).load()))
+            .collect();
+
+        let mut servers = Vec::new();
+        for server in app.servers.live().iter().chain(app.servers.gone().iter()) {
+            let guard = server.lock().await;
+            servers.push(ServerSnapshot::new(
+                &guard.accessible_name,
+                guard.bus_name.as_str(),
+                &guard.stats,
+                guard.departed.is_some(),
+                guard.tree.clone(),
+            ));
+        }
+
+        Snapshot {
+            seconds: app.history.lock().unwrap().seconds(),
+            rate: app.rt_stats.rate.load(),
+            peak: app.rt_stats.max.load(),
+            mean: app.rt_stats.mean.load(),
+            total: app.tally.total.load(),
+            windows: app.rt_stats.windows.lock().unwrap().to_vec(),
+            filtered: app.tally.filtered.load(),
+            categories,
+            servers,
+            alerts: app.alerts.active(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "[{:>6}s] rate: {}/s peak: {}/s mean: {}/s total: {} filtered: {}\n",
+            self.seconds, self.rate, self.peak, self.mean, self.total, self.filtered
+        );
+
+        text.push_str("  ");
+        for window in self.windows.iter() {
+            let _ = write!(
+                text,
+                " {}: {:.1}/s peak: {}/s",
+                window.label(),
+                window.mean,
+                window.peak
+            );
+        }
+        text.push('\n');
+
+        for alert in self.alerts.iter() {
+            let _ = writeln!(text, "   ALERT {}: {}", alert.rule.name(), alert.message);
+        }
+
+        text.push_str("  ");
+        for (name, count) in self.categories.iter() {
+            let _ = write!(text, " {name}: {count}");
+        }
+        text.push('\n');
+
+        let us = |value: Option<u128>| value.map_or("-".to_string(), |v| format!("{v}us"));
+        for server in self.servers.iter() {
+            let _ = writeln!(
+                text,
+                "   {} ({}){}: samples: {} min: {} max: {} avg: {} σ: {}",
+                server.name,
+                server.bus_name,
+                if server.departed { " departed" } else { "" },
+                server.samples,
+                us(server.min_us),
+                us(server.max_us),
+                us(server.mean_us),
+                us(server.std_dev_us)
+            );
+            text.push_str("     ");
+            for (label, value) in server.percentiles_us.iter() {
+                let _ = write!(text, " {label}: {}", us(*value));
+            }
+            text.push('\n');
+
+            text.push_str("     ");
+            match server.availability {
+                Some(availability) => {
+                    let _ = write!(text, " available: {availability:.1}%");
+                }
+                None => text.push_str(" not measured yet"),
+            }
+            if let Some(secs) = server.last_responsive_secs_ago {
+                let _ = write!(text, " last responsive: {secs}s ago");
+            }
+            let _ = write!(text, " timeouts: {}", server.timeouts);
+            for (name, count) in server.errors.iter() {
+                let _ = write!(text, " {name}: {count}");
+            }
+            text.push('\n');
+
+            if let Some(tree) = &server.tree {
+                let _ = writeln!(text, "      {tree}");
+            }
+        }
+
+        text
+    }
+}
+
+/// Print a snapshot every `interval` until interrupted, or terminated as by systemd.
+pub async fn run(app: Arc<App>, interval: Duration, format: OutputFormat) -> Result<()> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut every = tokio::time::interval(interval);
+    // The first tick completes immediately, there is nothing to report yet.
+    every.tick().await;
+    let mut announcer = Announcer::default();
+
+    loop {
+        tokio::select! {
+            _ = every.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = terminate.recv() => return Ok(()),
+        }
+
+        let snapshot = Snapshot::take(&app).await;
+        match format {
+            OutputFormat::Text => print!("{}", snapshot.to_text()),
+            OutputFormat::Json => println!("{}", serde_json::to_string(&snapshot)?),
+            OutputFormat::Speech => {
+                for line in announcer.announce(&snapshot) {
+                    println!("{line}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+// Each power of two is split into this many linear sub-buckets,
+// which bounds the relative error of a quantile to 1/16th.
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+// Values below `SUB_BUCKETS` nanoseconds get a bucket each, above that every
+// power of two up to 2^63 gets `SUB_BUCKETS` buckets.
+const BUCKETS: usize = (64 - SUB_BUCKET_BITS as usize + 1) * SUB_BUCKETS;
+
+/// A log-linear histogram of durations, for quantiles in constant memory.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    counts: Vec<u32>,
+    total: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            counts: vec![0; BUCKETS],
+            total: 0,
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, value: Duration) {
+        let nanos = u64::try_from(value.as_nanos()).unwrap_or(u64::MAX);
+        let bucket = &mut self.counts[bucket_of(nanos)];
+        *bucket = bucket.saturating_add(1);
+        self.total += 1;
+    }
+
+    /// The value below which a fraction `q` of the recorded values lie, `q` in 0.0..=1.0.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.total == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += u64::from(*count);
+            if seen >= rank {
+                return Some(Duration::from_nanos(midpoint_of(bucket)));
+            }
+        }
+
+        None
+    }
+}
+
+fn bucket_of(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+
+    let exponent = 63 - nanos.leading_zeros();
+    let sub_bucket = (nanos >> (exponent - SUB_BUCKET_BITS)) as usize & (SUB_BUCKETS - 1);
+    (exponent - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS + sub_bucket
+}
+
+// The middle of the range of values that fall in `bucket`.
+fn midpoint_of(bucket: usize) -> u64 {
+    if bucket < SUB_BUCKETS {
+        return bucket as u64;
+    }
+
+    let exponent = (bucket / SUB_BUCKETS) as u32 + SUB_BUCKET_BITS - 1;
+    let sub_bucket = (bucket % SUB_BUCKETS) as u64;
+    let width = 1u64 << (exponent - SUB_BUCKET_BITS);
+    let lower = (1u64 << exponent) + sub_bucket * width;
+    lower + width / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A value and the midpoint of its bucket differ by at most 1/16th of the value.
+    fn assert_close(value: u64, approximation: u64) {
+        let error = value.abs_diff(approximation);
+        assert!(
+            error <= value / SUB_BUCKETS as u64,
+            "{approximation} is not within 1/16th of {value}"
+        );
+    }
+
+    #[test]
+    fn buckets_round_trip() {
+        let powers = (4..64).map(|k| 1u64 << k);
+        let values = [0, 1, 15, 16, 17, 31, 32, u64::MAX]
+            .into_iter()
+            .chain(powers);
+
+        for value in values {
+            let bucket = bucket_of(value);
+            assert!(bucket < BUCKETS, "bucket {bucket} of {value} out of range");
+
+            let midpoint = midpoint_of(bucket);
+            assert_eq!(bucket_of(midpoint), bucket, "midpoint of {value}'s bucket");
+            assert_close(value, midpoint);
+        }
+    }
+
+    #[test]
+    fn small_values_are_exact() {
+        for value in 0..SUB_BUCKETS as u64 {
+            assert_eq!(midpoint_of(bucket_of(value)), value);
+        }
+        assert_eq!(bucket_of(15), 15);
+        assert_eq!(bucket_of(16), 16);
+    }
+
+    #[test]
+    fn quantiles_of_a_uniform_distribution() {
+        let mut histogram = Histogram::default();
+        assert_eq!(histogram.quantile(0.5), None);
+
+        for micros in 1..=1000 {
+            histogram.record(Duration::from_micros(micros));
+        }
+
+        for (q, expected) in [(0.5, 500_000), (0.99, 990_000), (1.0, 1_000_000)] {
+            let quantile = histogram.quantile(q).unwrap().as_nanos() as u64;
+            assert_close(expected, quantile);
+        }
+        assert_close(1_000, histogram.quantile(0.0).unwrap().as_nanos() as u64);
+    }
+}
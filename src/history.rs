@@ -0,0 +1,286 @@
+use crate::cli::Config;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// How much time a sample of the history covers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Resolution {
+    #[default]
+    Second,
+    Minute,
+    Hour,
+}
+
+impl Resolution {
+    pub fn secs(self) -> usize {
+        match self {
+            Resolution::Second => 1,
+            Resolution::Minute => 60,
+            Resolution::Hour => 3600,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Resolution::Second => "second",
+            Resolution::Minute => "minute",
+            Resolution::Hour => "hour",
+        }
+    }
+
+    /// The next coarser resolution, the hours stay hours.
+    pub fn zoom_out(self) -> Resolution {
+        match self {
+            Resolution::Second => Resolution::Minute,
+            _ => Resolution::Hour,
+        }
+    }
+
+    /// The next finer resolution, the seconds stay seconds.
+    pub fn zoom_in(self) -> Resolution {
+        match self {
+            Resolution::Hour => Resolution::Minute,
+            _ => Resolution::Second,
+        }
+    }
+}
+
+/// The events in a minute or an hour.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Sample {
+    pub events: u64,
+    /// Most events in one of its seconds.
+    pub peak: u64,
+}
+
+impl Sample {
+    fn add(&mut self, other: Sample) {
+        self.events += other.events;
+        self.peak = self.peak.max(other.peak);
+    }
+}
+
+/// A ring buffer of samples of one resolution, the oldest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct Tier<T> {
+    /// Second of the session the oldest sample starts at.
+    pub start: usize,
+    pub step_secs: usize,
+    pub samples: VecDeque<T>,
+    #[serde(skip)]
+    capacity: usize,
+}
+
+impl<T> Tier<T> {
+    fn new(resolution: Resolution, capacity: usize) -> Tier<T> {
+        Tier {
+            start: 0,
+            step_secs: resolution.secs(),
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, sample: T) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+            self.start += self.step_secs;
+        }
+        self.samples.push_back(sample);
+    }
+}
+
+/// Events per second over the session, in tiers: the recent past by the second,
+/// older times by the minute and by the hour. Each tier holds a fixed number of samples,
+/// so a run of days takes no more memory than a run of hours.
+#[derive(Debug, Clone, Serialize)]
+pub struct History {
+    seconds: Tier<u64>,
+    minutes: Tier<Sample>,
+    hours: Tier<Sample>,
+    // The minute and the hour being filled.
+    #[serde(skip)]
+    this_minute: Sample,
+    #[serde(skip)]
+    this_hour: Sample,
+    #[serde(skip)]
+    counted: usize,
+}
+
+impl History {
+    pub fn new(config: &Config) -> History {
+        History {
+            seconds: Tier::new(Resolution::Second, config.secs_capacity),
+            minutes: Tier::new(Resolution::Minute, config.minutes_capacity),
+            hours: Tier::new(Resolution::Hour, config.hours_capacity),
+            this_minute: Sample::default(),
+            this_hour: Sample::default(),
+            counted: 0,
+        }
+    }
+
+    /// Add the events of the second that just ended.
+    pub fn push(&mut self, events: u64) {
+        self.counted += 1;
+        self.seconds.push(events);
+
+        self.this_minute.add(Sample {
+            events,
+            peak: events,
+        });
+        if self.counted % Resolution::Minute.secs() != 0 {
+            return;
+        }
+        let minute = std::mem::take(&mut self.this_minute);
+        self.minutes.push(minute);
+
+        self.this_hour.add(minute);
+        if self.counted % Resolution::Hour.secs() == 0 {
+            let hour = std::mem::take(&mut self.this_hour);
+            self.hours.push(hour);
+        }
+    }
+
+    /// Seconds counted so far.
+    pub fn seconds(&self) -> usize {
+        self.counted
+    }
+
+    /// The events per second kept, the last second last.
+    pub fn recent(&mut self) -> &[u64] {
+        self.seconds.samples.make_contiguous()
+    }
+
+    /// The samples kept at `resolution`, as second of the session and sample, the oldest first.
+    pub fn samples(&self, resolution: Resolution) -> Vec<(usize, Sample)> {
+        let tier = |start: usize, step: usize, samples: Vec<Sample>| {
+            samples
+                .into_iter()
+                .enumerate()
+                .map(|(i, sample)| (start + i * step, sample))
+                .collect()
+        };
+
+        match resolution {
+            Resolution::Second => tier(
+                self.seconds.start,
+                1,
+                self.seconds
+                    .samples
+                    .iter()
+                    .map(|&events| Sample {
+                        events,
+                        peak: events,
+                    })
+                    .collect(),
+            ),
+            Resolution::Minute => tier(
+                self.minutes.start,
+                self.minutes.step_secs,
+                self.minutes.samples.iter().copied().collect(),
+            ),
+            Resolution::Hour => tier(
+                self.hours.start,
+                self.hours.step_secs,
+                self.hours.samples.iter().copied().collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(seconds: usize, minutes: usize, hours: usize) -> History {
+        History::new(&Config {
+            secs_capacity: seconds,
+            minutes_capacity: minutes,
+            hours_capacity: hours,
+            ..Config::default()
+        })
+    }
+
+    // Events in second `s` of the session: the minute number, with a spike halfway each minute.
+    fn events(s: usize) -> u64 {
+        let minute = (s / 60) as u64;
+        if s % 60 == 30 {
+            1000 + minute
+        } else {
+            minute
+        }
+    }
+
+    #[test]
+    fn seconds_drop_the_oldest() {
+        let mut history = history(10, 3, 2);
+        for s in 0..25 {
+            history.push(s as u64);
+        }
+
+        assert_eq!(history.seconds(), 25);
+        assert_eq!(history.recent(), (15..25).collect::<Vec<u64>>());
+        let samples = history.samples(Resolution::Second);
+        assert_eq!(samples.first().map(|(at, _)| *at), Some(15));
+        assert!(samples
+            .iter()
+            .all(|(at, sample)| sample.events == *at as u64 && sample.peak == *at as u64));
+    }
+
+    #[test]
+    fn minutes_roll_over_at_capacity() {
+        let mut history = history(900, 3, 2);
+        // Two hours, two minutes and a bit: the last minute is not complete.
+        let seconds = 2 * 3600 + 2 * 60 + 10;
+        for s in 0..seconds {
+            history.push(events(s));
+        }
+
+        let minutes = history.samples(Resolution::Minute);
+        let starts: Vec<usize> = minutes.iter().map(|(at, _)| *at).collect();
+        assert_eq!(starts, [119 * 60, 120 * 60, 121 * 60]);
+        for (at, sample) in minutes {
+            let minute = (at / 60) as u64;
+            assert_eq!(
+                sample.events,
+                59 * minute + 1000 + minute,
+                "minute {minute}"
+            );
+            assert_eq!(sample.peak, 1000 + minute, "minute {minute}");
+        }
+    }
+
+    #[test]
+    fn hours_roll_over_at_capacity() {
+        let mut history = history(900, 3, 2);
+        // Three and a half hours: two complete hours kept, the first one dropped.
+        for s in 0..3 * 3600 + 1800 {
+            history.push(events(s));
+        }
+
+        let hours = history.samples(Resolution::Hour);
+        let starts: Vec<usize> = hours.iter().map(|(at, _)| *at).collect();
+        assert_eq!(starts, [3600, 7200]);
+        for (at, sample) in hours {
+            let expected: u64 = (at..at + 3600).map(events).sum();
+            assert_eq!(sample.events, expected, "hour starting at {at}");
+            assert_eq!(
+                sample.peak,
+                1000 + (at / 60 + 59) as u64,
+                "hour starting at {at}"
+            );
+        }
+    }
+
+    #[test]
+    fn nothing_before_a_tier_fills() {
+        let mut history = history(900, 3, 2);
+        for s in 0..59 {
+            history.push(events(s));
+        }
+
+        assert!(history.samples(Resolution::Minute).is_empty());
+        assert!(history.samples(Resolution::Hour).is_empty());
+        assert_eq!(history.samples(Resolution::Second).len(), 59);
+    }
+}
@@ -1,41 +1,93 @@
 use atspi::{
     connection::AccessibilityConnection,
+    error::AtspiError,
     events::{
         document::DocumentEvents, focus::FocusEvents, keyboard::KeyboardEvents, mouse::MouseEvents,
         object::ObjectEvents, terminal::TerminalEvents, window::WindowEvents, AddAccessibleEvent,
         Event as AtspiEvent, EventListenerDeregisteredEvent, EventListenerRegisteredEvent,
-        LegacyAddAccessibleEvent, RemoveAccessibleEvent,
+        HasMatchRule, HasRegistryEventString, LegacyAddAccessibleEvent, RemoveAccessibleEvent,
     },
 };
-use crossterm::event::{self, Event, KeyCode};
-use ratatui::{
-    backend::Backend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
-    widgets::{Block, Borders, Cell, ListItem, Row, Sparkline, Table},
-    Frame, Terminal,
-};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use crossterm::event::{self, Event, KeyCode, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{backend::Backend, Terminal};
+use serde::Serialize;
 use std::{
-    collections::HashSet,
     io,
+    path::PathBuf,
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc, Mutex,
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
+use tokio::sync::watch;
 use tokio_stream::StreamExt;
-use zbus::zvariant::ObjectPath;
+use zbus::{zvariant::ObjectPath, MessageStream, MessageType};
+
+mod alert;
+use alert::Alerts;
 
 mod bus;
-use bus::Servers;
+use bus::{Membership, Servers};
+
+mod category;
+use category::{Category, CategorySeries, MemberTallies};
+
+mod cli;
+use cli::{Cli, Config};
+
+mod control;
+use control::{Marker, ResetScope};
+
+mod crawler;
+
+mod errors;
+use errors::ErrorStore;
+
+mod event_log;
+use event_log::EventLog;
+
+mod filter;
+use filter::Filter;
+
+mod headless;
+
+mod histogram;
+
+mod history;
+use history::History;
+
+#[cfg(feature = "prometheus")]
+mod metrics;
+
+mod report;
+use report::Report;
+
+mod record;
+use record::{Recorder, Replay};
+
+mod signal;
+use signal::SignalHeader;
+
+mod speech;
 
 mod terminal;
 use terminal::{restore_terminal, setup_terminal};
 
+mod tree;
+use tree::Explorer;
+
+mod ui;
+use ui::{talker_bus_names, ui, Pane, Preset, UiState};
+
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-const TICK_MS: Duration = Duration::from_millis(100);
+// Markers kept, the oldest go first.
+const MAX_MARKERS: usize = 1000;
+
+// Membership changes waiting for the tracker, which may be busy asking a slow application.
+const MEMBERSHIP_QUEUE: usize = 64;
 
 // The path to the root-Accessible object on the AT-SPI2 bus
 const ACCESSIBLE_ROOT_PATH: ObjectPath<'static> =
@@ -81,7 +133,16 @@ impl Counter {
     }
 }
 
-#[derive(Debug, Default)]
+// A clone is a counter that starts at the value the original has now.
+impl Clone for Counter {
+    fn clone(&self) -> Self {
+        Counter {
+            counter: AtomicU64::new(self.load()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 struct ScoreBoard {
     // Categorized counters
     mouse: Counter,
@@ -97,6 +158,9 @@ struct ScoreBoard {
     other_event: Counter,
     error: Counter,
 
+    // Events the filter kept out
+    filtered: Counter,
+
     // Global counters
     tick_counter: Counter,
     secs_counter: Counter,
@@ -104,85 +168,258 @@ struct ScoreBoard {
     total: Counter,
 }
 
-#[derive(Debug, Default)]
+impl ScoreBoard {
+    /// The counter that keeps score of `category`.
+    fn counter(&self, category: Category) -> &Counter {
+        match category {
+            Category::Keyboard => &self.keyboard,
+            Category::Mouse => &self.mouse,
+            Category::Focus => &self.focus,
+            Category::Window => &self.window,
+            Category::Document => &self.document,
+            Category::Object => &self.object,
+            Category::Terminal => &self.terminal,
+            Category::Cache => &self.cache,
+            Category::Listeners => &self.listeners,
+            Category::Available => &self.available,
+            Category::Other => &self.other_event,
+            Category::Error => &self.error,
+        }
+    }
+}
+
+// Seconds the rolling stats are taken over, like the load average: 1, 5 and 15 minutes.
+const WINDOWS: [usize; 3] = [60, 300, 900];
+
+/// Mean and peak rate over the last `secs` seconds.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Window {
+    pub secs: usize,
+    pub mean: f64,
+    pub peak: u64,
+}
+
+impl Window {
+    // Over the tail of the recent seconds, or all of it while the session is younger than the window.
+    fn over(secs: usize, recent: &[u64]) -> Window {
+        let tail = &recent[recent.len().saturating_sub(secs)..];
+        Window {
+            secs,
+            mean: tail.iter().sum::<u64>() as f64 / tail.len().max(1) as f64,
+            peak: tail.iter().copied().max().unwrap_or(0),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}m", self.secs / 60)
+    }
+}
+
+#[derive(Debug)]
 struct RtStats {
     pub rate: Counter,
     pub max: Counter,
     pub mean: Counter,
+    pub windows: Mutex<[Window; WINDOWS.len()]>,
+    // The second the counters were last reset at, the mean is taken from there.
+    pub since: Counter,
+}
+
+impl Clone for RtStats {
+    fn clone(&self) -> Self {
+        RtStats {
+            rate: self.rate.clone(),
+            max: self.max.clone(),
+            mean: self.mean.clone(),
+            windows: Mutex::new(*self.windows.lock().unwrap()),
+            since: self.since.clone(),
+        }
+    }
+}
+
+impl Default for RtStats {
+    fn default() -> Self {
+        RtStats {
+            rate: Counter::default(),
+            max: Counter::default(),
+            mean: Counter::default(),
+            windows: Mutex::new(WINDOWS.map(|secs| Window::over(secs, &[]))),
+            since: Counter::default(),
+        }
+    }
 }
 
 struct App {
+    // When the session started
+    started: SystemTime,
+
     // The bus servers
     servers: Servers,
 
     // Keeping the score
     tally: ScoreBoard,
 
-    // Error set
-    error_set: Arc<Mutex<HashSet<String>>>,
+    // Keeping the score per signal member within each category
+    members: MemberTallies,
+
+    // The latest events, for the event log
+    event_log: EventLog,
+
+    // Which events are counted, changes are picked up by the event registration
+    filter: watch::Sender<Filter>,
+
+    // The errors met, by kind
+    errors: ErrorStore,
 
     // Tick/secs stats
     rt_stats: RtStats,
 
+    // Event storms, raised from the per second stats
+    alerts: Alerts,
+
     // The counter data stores
     tick_data: Mutex<Vec<u64>>,
-    secs_data: Mutex<Vec<u64>>,
+    history: Mutex<History>,
+    category_series: CategorySeries,
+
+    // Named points on the timeline
+    markers: Mutex<Vec<Marker>>,
 }
 
 impl App {
-    async fn new() -> Result<App> {
-        // Get a connection to the AT-SPI D-Bus service, without registering for events.
-        let a11y_conn = atspi::connection::AccessibilityConnection::new().await?;
-
-        // Get the bus servers
-        let servers = Servers::new(a11y_conn.connection()).await?;
-
+    fn new(servers: Servers, config: &Config, filter: Filter) -> App {
         // Init counters
         let tally = ScoreBoard::default();
+        let members = MemberTallies::default();
+        let (filter, _) = watch::channel(filter);
+        let event_log = EventLog::new(config.event_log_capacity);
 
-        // error map
-        let error_set = Arc::new(Mutex::new(HashSet::new()));
+        // error store
+        let errors = ErrorStore::new(config.error_capacity);
 
         // Init rate stats
         let rt_stats = RtStats::default();
+        let alerts = Alerts::new(config);
 
         // Init counter data stores
-        let tick_data = Mutex::new(vec![0; 200]);
-        let secs_data = Mutex::new(Vec::with_capacity(1800)); // 30 minutes
+        let tick_data = Mutex::new(vec![0; config.tick_history]);
+        let history = Mutex::new(History::new(config));
+        let category_series = CategorySeries::new(config.mix_history);
 
-        Ok(App {
+        App {
+            started: SystemTime::now(),
             servers,
             tally,
+            members,
+            event_log,
+            filter,
             rt_stats,
+            alerts,
             tick_data,
-            secs_data,
-            error_set,
-        })
+            history,
+            category_series,
+            errors,
+            markers: Mutex::new(Vec::new()),
+        }
     }
 
-    // Event -> update counters.
-    fn on_event(&self, event: Result<AtspiEvent>) {
-        match event {
-            Ok(AtspiEvent::Mouse(_)) => self.tally.mouse.incr(),
-            Ok(AtspiEvent::Keyboard(_)) => self.tally.keyboard.incr(),
-            Ok(AtspiEvent::Focus(_)) => self.tally.focus.incr(),
-            Ok(AtspiEvent::Window(_)) => self.tally.window.incr(),
-            Ok(AtspiEvent::Document(_)) => self.tally.document.incr(),
-            Ok(AtspiEvent::Object(_)) => self.tally.object.incr(),
-            Ok(AtspiEvent::Terminal(_)) => self.tally.terminal.incr(),
-            Ok(AtspiEvent::Cache(_)) => self.tally.cache.incr(),
-            Ok(AtspiEvent::Listener(_)) => self.tally.listeners.incr(),
-            Ok(AtspiEvent::Available(_)) => self.tally.available.incr(),
-            Ok(_) => self.tally.other_event.incr(),
-            Err(e) => {
-                self.tally.error.incr();
-                let msg = format!("{e}");
-                let mut set = self.error_set.lock().unwrap();
-                if !set.contains(&msg) {
-                    set.insert(msg);
+    /// A copy of the state as it is now, to show while the screen is paused.
+    fn snapshot(&self) -> App {
+        App {
+            started: self.started,
+            servers: self.servers.clone(),
+            tally: self.tally.clone(),
+            members: self.members.clone(),
+            event_log: self.event_log.clone(),
+            filter: watch::channel(self.filter.borrow().clone()).0,
+            errors: self.errors.clone(),
+            rt_stats: self.rt_stats.clone(),
+            alerts: self.alerts.clone(),
+            tick_data: Mutex::new(self.tick_data.lock().unwrap().clone()),
+            history: Mutex::new(self.history.lock().unwrap().clone()),
+            category_series: self.category_series.clone(),
+            markers: Mutex::new(self.markers.lock().unwrap().clone()),
+        }
+    }
+
+    /// Place a marker named `name` at the current second.
+    fn mark(&self, name: &str) {
+        let second = self.history.lock().unwrap().seconds();
+        let mut markers = self.markers.lock().unwrap();
+        if markers.len() == MAX_MARKERS {
+            markers.remove(0);
+        }
+        tracing::info!("Marker at {second}s: {name}");
+        markers.push(Marker {
+            second,
+            name: name.to_string(),
+        });
+    }
+
+    /// Zero the counters in `scope` for a clean baseline, and mark the timeline where it was done.
+    /// The history, the event log and the errors are kept.
+    fn reset(&self, scope: ResetScope) {
+        match scope {
+            ResetScope::All => {
+                for category in Category::ALL {
+                    self.tally.counter(category).reset();
                 }
+                self.tally.filtered.reset();
+                self.tally.total.reset();
+                self.tally.total_seconds.reset();
+                self.rt_stats.max.set(0);
+                self.rt_stats.mean.set(0);
+                let second = self.history.lock().unwrap().seconds();
+                self.rt_stats.since.set(second as u64);
+                self.members.reset(None);
+                self.category_series.reset(None);
+                self.servers.reset_counts(None);
+                self.mark("reset");
+            }
+            ResetScope::Category(category) => {
+                self.tally.counter(category).reset();
+                self.members.reset(Some(category));
+                self.category_series.reset(Some(category));
+                self.servers.reset_counts(Some(category));
+                self.mark(&format!("reset {}", category.name()));
             }
         }
+    }
+
+    // Event -> update counters.
+    // `header` is absent if the message could not be received at all.
+    fn on_event(&self, header: Option<&SignalHeader>, event: Result<AtspiEvent>) {
+        let category = Category::of(&event);
+        if !self
+            .filter
+            .borrow()
+            .matches(category, header, &self.servers)
+        {
+            self.tally.filtered.incr();
+            return;
+        }
+        self.tally.counter(category).incr();
+
+        let sender = header.and_then(|header| header.sender.as_deref());
+        self.servers.count_event(sender, category);
+
+        if let Some(member) = header.and_then(|header| header.member.as_deref()) {
+            self.members.count(category, member);
+        }
+
+        let error = event.err().map(|e| {
+            let message = e.to_string();
+            self.errors
+                .add(errors::ErrorKind::of(e.as_ref()), message.clone(), header);
+            message
+        });
+
+        let app_name = sender.map_or_else(String::new, |sender| {
+            self.servers
+                .accessible_name(sender)
+                .unwrap_or_else(|| sender.to_string())
+        });
+        self.event_log.push(category, app_name, header, error);
         self.tally.tick_counter.incr();
         self.tally.secs_counter.incr();
         self.tally.total.incr();
@@ -210,29 +447,45 @@ impl App {
 
         self.rt_stats.rate.set(value);
         self.tally.total_seconds.add(value);
+        self.members.on_second();
+        self.category_series
+            .on_second(Category::ALL.map(|category| self.tally.counter(category).load()));
 
         // Per second data:
-        let mut data = self.secs_data.lock().unwrap();
-        data.push(value);
-        let len = data.len();
-        let mean = self.tally.total_seconds.load() / len as u64;
+        let mut history = self.history.lock().unwrap();
+        history.push(value);
+        let seconds = history.seconds();
+        let counted = (seconds as u64 - self.rt_stats.since.load()).max(1);
+        let mean = self.tally.total_seconds.load() / counted;
         self.rt_stats.mean.set(mean);
+        let recent = history.recent();
+        *self.rt_stats.windows.lock().unwrap() = WINDOWS.map(|secs| Window::over(secs, recent));
+
+        let talkers: Vec<_> = self
+            .servers
+            .talkers()
+            .iter()
+            .map(|talker| {
+                let name = talker.accessible_name.clone();
+                (talker.bus_name.clone(), name, talker.counts.total())
+            })
+            .collect();
+        self.alerts.on_second(seconds, recent, &talkers);
     }
 }
 
-async fn setup_atspi() -> Result<AccessibilityConnection> {
+async fn setup_atspi(filter: &Filter) -> Result<AccessibilityConnection> {
     // Get a connection to the AT-SPI D-Bus service
     let atspi: AccessibilityConnection = AccessibilityConnection::new().await?;
 
-    // Register for events
-    atspi.register_event::<MouseEvents>().await?;
-    atspi.register_event::<KeyboardEvents>().await?;
-    atspi.register_event::<FocusEvents>().await?;
-    atspi.register_event::<WindowEvents>().await?;
-    atspi.register_event::<DocumentEvents>().await?;
-    atspi.register_event::<ObjectEvents>().await?;
-    atspi.register_event::<TerminalEvents>().await?;
+    // Register for the event families the filter lets through
+    for category in Category::ALL {
+        if filter.admits(category) {
+            register_family(&atspi, category, true).await?;
+        }
+    }
 
+    // The cache events tell applications joining and leaving, these are always needed.
     atspi.register_event::<AddAccessibleEvent>().await?;
     atspi.register_event::<LegacyAddAccessibleEvent>().await?;
     atspi.register_event::<RemoveAccessibleEvent>().await?;
@@ -246,29 +499,93 @@ async fn setup_atspi() -> Result<AccessibilityConnection> {
     Ok(atspi)
 }
 
+/// Register or deregister for the events of `category`.
+/// Categories that are not an event family of their own are left alone.
+async fn register_family(
+    atspi: &AccessibilityConnection,
+    category: Category,
+    register: bool,
+) -> std::result::Result<(), AtspiError> {
+    async fn set<T: HasRegistryEventString + HasMatchRule>(
+        atspi: &AccessibilityConnection,
+        register: bool,
+    ) -> std::result::Result<(), AtspiError> {
+        if register {
+            atspi.register_event::<T>().await
+        } else {
+            atspi.deregister_event::<T>().await
+        }
+    }
+
+    match category {
+        Category::Mouse => set::<MouseEvents>(atspi, register).await,
+        Category::Keyboard => set::<KeyboardEvents>(atspi, register).await,
+        Category::Focus => set::<FocusEvents>(atspi, register).await,
+        Category::Window => set::<WindowEvents>(atspi, register).await,
+        Category::Document => set::<DocumentEvents>(atspi, register).await,
+        Category::Object => set::<ObjectEvents>(atspi, register).await,
+        Category::Terminal => set::<TerminalEvents>(atspi, register).await,
+        _ => Ok(()),
+    }
+}
+
+/// Keep the registered event families in line with the filter as it changes.
+async fn follow_filter(atspi: AccessibilityConnection, mut changes: watch::Receiver<Filter>) {
+    let mut registered = changes.borrow_and_update().clone();
+
+    while changes.changed().await.is_ok() {
+        let filter = changes.borrow_and_update().clone();
+
+        for category in Category::ALL {
+            let admits = filter.admits(category);
+            if admits == registered.admits(category) {
+                continue;
+            }
+            if let Err(e) = register_family(&atspi, category, admits).await {
+                tracing::error!("Cannot change registration of {}: {e}", category.name());
+            }
+        }
+
+        registered = filter;
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Create the app's state
-    let app = Arc::new(App::new().await.expect("creation of app-state"));
+    let options = Cli::parse();
+    let config = Config::load(&options)
+        .unwrap_or_else(|e| Cli::command().error(ErrorKind::ValueValidation, e).exit());
 
     // Setup tracing
     #[cfg(feature = "tracing")]
     console_subscriber::init();
 
-    // Obtain a connection for events.
-    let atspi_conn = setup_atspi().await?;
-    let mut events = atspi_conn.event_stream();
+    let filter = options.filter.clone().unwrap_or_default();
 
-    // Trigger counters.
-    let app_clone = Arc::clone(&app);
-    tokio::spawn(async move {
-        while let Some(event) = events.next().await {
-            app_clone.on_event(event.map_err(Into::into))
+    let app = match &options.replay {
+        Some(path) => {
+            // Replay a recording instead of listening to the bus.
+            let replay = Replay::open(path)?;
+            let app = Arc::new(App::new(replay.servers(), &config, filter));
+            tokio::spawn(replay.run(Arc::clone(&app), options.speed));
+            app
         }
-
-        // The event stream has ended.
-        tracing::info!("Event stream ended");
-    });
+        None => {
+            // Get a connection to the AT-SPI D-Bus service, without registering for events.
+            let a11y_conn = AccessibilityConnection::new().await?;
+
+            // Get the bus servers and create the app's state
+            let servers = Servers::new(a11y_conn.connection()).await?;
+            let app = Arc::new(App::new(servers, &config, filter));
+
+            let recorder = match &options.record {
+                Some(path) => Some(Recorder::create(path, &app.servers)?),
+                None => None,
+            };
+            listen(Arc::clone(&app), recorder).await?;
+            app
+        }
+    };
 
     let app_clone = Arc::clone(&app);
     tokio::spawn(async move {
@@ -280,46 +597,165 @@ async fn main() -> Result<()> {
         }
     });
 
-    // Ping bus servers 2s. -> acquire response time.
+    // Ping bus servers each ping interval (2s. by default) -> acquire response time.
+    // A replay has no bus servers to ping.
     let app_clone = Arc::clone(&app);
+    let ping_config = config.clone();
     tokio::spawn(async move {
-        let mut in_between = tokio::time::interval(Duration::from_millis(20));
-        let mut every_other_second = tokio::time::interval(Duration::from_secs(2));
+        let mut in_between = tokio::time::interval(ping_config.ping_gap);
+        let mut each_round = tokio::time::interval(ping_config.ping_interval);
 
         loop {
             let app_clone = Arc::clone(&app_clone);
-            every_other_second.tick().await;
+            each_round.tick().await;
 
-            for server in app_clone.servers.bus.iter() {
+            for server in app_clone.servers.live() {
                 in_between.tick().await;
 
                 let Ok(mut guard) = server.try_lock() else {
                     continue;
                 };
 
-                if let Some(dur) = guard.acquire_rtt().await {
-                    guard.update_rtt_stats(dur);
-                }
+                let probe = guard.acquire_rtt(ping_config.rtt_deadline).await;
+                guard.update_rtt_stats(probe);
             }
         }
     });
 
-    // setup terminal
-    let mut terminal = setup_terminal().expect("setup terminal");
+    // Measure the accessible trees of the bus servers in the background.
+    // A replay has no trees to measure.
+    if config.crawl_rate > 0 {
+        tokio::spawn(crawler::run(
+            Arc::clone(&app),
+            config.crawl_rate,
+            config.crawl_interval,
+        ));
+    }
 
-    let app_clone = Arc::clone(&app);
-    let res = run_app(&mut terminal, app_clone, TICK_MS);
+    // Other processes can place markers and reset the counters.
+    tokio::spawn(control::listen_signals(Arc::clone(&app)));
+
+    #[cfg(feature = "prometheus")]
+    if let Some(addr) = options.metrics {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        tokio::spawn(metrics::serve(listener, Arc::clone(&app)));
+    }
 
-    // restore terminal
-    restore_terminal(&mut terminal)?;
+    let report_dir = options
+        .report_dir
+        .clone()
+        .or_else(report::default_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    if options.headless {
+        let interval = Duration::from_secs(options.interval);
+        headless::run(Arc::clone(&app), interval, options.format).await?;
+    } else {
+        // setup terminal
+        let mut terminal = setup_terminal().expect("setup terminal");
+
+        let app_clone = Arc::clone(&app);
+        let res = run_app(
+            &mut terminal,
+            app_clone,
+            config.tick,
+            &report_dir,
+            options.layout,
+        );
+
+        // restore terminal
+        restore_terminal(&mut terminal)?;
+
+        if let Err(err) = res {
+            tracing::error!("Error: {err}");
+        }
+    }
 
-    if let Err(err) = res {
-        tracing::error!("Error: {err}");
+    // Keep what was learned in this session.
+    if !options.no_report {
+        match Report::take(&app).await.write(&report_dir) {
+            Ok(path) => eprintln!("Session report written to {}", path.display()),
+            Err(e) => eprintln!("Cannot write session report: {e}"),
+        }
     }
 
     Ok(())
 }
 
+/// Listen to the bus and trigger the counters, recording the signals if asked to.
+async fn listen(app: Arc<App>, mut recorder: Option<Recorder>) -> Result<()> {
+    // Obtain a connection for events.
+    // We read the messages ourselves, rather than `event_stream`, to learn each signal's header.
+    let filter = app.filter.borrow().clone();
+    let atspi_conn = setup_atspi(&filter).await?;
+    let mut messages = MessageStream::from(atspi_conn.connection());
+
+    // Follow the applications joining and leaving the bus.
+    let (memberships, membership_rx) = tokio::sync::mpsc::channel(MEMBERSHIP_QUEUE);
+    let conn = atspi_conn.connection().clone();
+    let tracker_app = Arc::clone(&app);
+    tokio::spawn(async move {
+        if let Err(e) = tracker_app.servers.track(&conn, membership_rx).await {
+            tracing::error!("Tracking bus servers stopped: {e}");
+        }
+    });
+
+    // Changing the filter changes the event families registered for.
+    tokio::spawn(follow_filter(atspi_conn, app.filter.subscribe()));
+
+    tokio::spawn(async move {
+        while let Some(msg) = messages.next().await {
+            let msg = match msg {
+                Ok(msg) => msg,
+                Err(e) => {
+                    app.on_event(None, Err(e.into()));
+                    continue;
+                }
+            };
+
+            // The bus daemon's own signals are not AT-SPI traffic.
+            if msg.message_type() != MessageType::Signal
+                || msg.interface().as_deref() == Some("org.freedesktop.DBus")
+            {
+                continue;
+            }
+
+            let header = SignalHeader::from(&*msg);
+            let event = AtspiEvent::try_from(&*msg).map_err(Into::into);
+
+            // A sender not known yet may be an application that joined, or one that
+            // did not answer before. The known ones are left out, a full queue drops the
+            // rest: their next signal tells again.
+            let membership = event
+                .as_ref()
+                .ok()
+                .and_then(Membership::of)
+                .or_else(|| header.sender.clone().map(Membership::Joined));
+            match membership {
+                Some(Membership::Joined(name)) if app.servers.is_known(&name) => {}
+                Some(membership) => {
+                    let _ = memberships.try_send(membership);
+                }
+                None => {}
+            }
+
+            if let Some(rec) = recorder.as_mut() {
+                if let Err(e) = rec.record(Some(&header), &event, &app.servers) {
+                    tracing::error!("Recording stopped: {e}");
+                    recorder = None;
+                }
+            }
+
+            app.on_event(Some(&header), event)
+        }
+
+        // The event stream has ended.
+        tracing::info!("Event stream ended");
+    });
+
+    Ok(())
+}
+
 /// Returns the remaining time until the next tick, or zero if the next tick is overdue.
 fn get_remaining_tick_time(tick_dur: Duration, last_tick: Instant) -> Duration {
     tick_dur
@@ -331,19 +767,120 @@ fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     app: Arc<App>,
     tick_dur: Duration,
+    report_dir: &std::path::Path,
+    layout: Preset,
 ) -> io::Result<()> {
     let mut last_tick = Instant::now();
+    let mut ui_state = UiState {
+        layout,
+        ..UiState::default()
+    };
+    let mut key_pressed = true;
+    // While paused, what the screen shows: the state as it was when the pause began.
+    let mut frozen: Option<Arc<App>> = None;
 
     loop {
-        let app_clone = Arc::clone(&app);
-        terminal.draw(|f| ui(f, app_clone))?;
+        let shown = Arc::clone(frozen.as_ref().unwrap_or(&app));
+
+        // While paused, the screen changes only with the keys pressed.
+        if key_pressed || !ui_state.paused {
+            let app_clone = Arc::clone(&shown);
+            terminal.draw(|f| ui(f, app_clone, &ui_state))?;
+        }
+        key_pressed = false;
 
         let timeout = get_remaining_tick_time(tick_dur, last_tick);
 
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if let KeyCode::Char('q') = key.code {
-                    return Ok(());
+            let event = event::read()?;
+            if let Event::Resize(..) = event {
+                key_pressed = true;
+            }
+            if let Event::Mouse(mouse) = event {
+                key_pressed = on_mouse(&shown, &mut ui_state, mouse);
+            }
+            if let Event::Key(key) = event {
+                key_pressed = true;
+                // While editing the filter or naming a marker, the keys go there.
+                if ui_state.filter_input.is_some() {
+                    edit_filter(&app, &mut ui_state, key.code);
+                } else if ui_state.marker_input.is_some() {
+                    edit_marker(&app, &mut ui_state, key.code);
+                } else if on_tree_key(&mut ui_state, key.code) {
+                    // The tree explorer took the key.
+                } else if ui_state.show_log && on_log_key(&shown, &mut ui_state, key.code) {
+                    // The event log took the key.
+                } else if ui_state.show_errors && on_errors_key(&shown, &mut ui_state, key.code) {
+                    // The error details took the key.
+                } else if ui_state.show_help {
+                    if matches!(key.code, KeyCode::Esc | KeyCode::Char('?' | 'q')) {
+                        ui_state.show_help = false;
+                    }
+                } else if on_view_key(&mut ui_state, key.code) {
+                    // A pop-up over the dashboard took the key.
+                } else {
+                    match key.code {
+                        KeyCode::Char('q') => return Ok(()),
+                        KeyCode::Char('s') => ui_state.talker_sort = ui_state.talker_sort.next(),
+                        KeyCode::Char('r') => ui_state.talker_sort_reversed ^= true,
+                        KeyCode::Char('c') => {
+                            ui_state.selected_category = ui_state.selected_category.next()
+                        }
+                        KeyCode::Tab => ui_state.focus = ui_state.focus.next(),
+                        KeyCode::BackTab => ui_state.focus = ui_state.focus.prev(),
+                        KeyCode::Up | KeyCode::Char('k') => select(&shown, &mut ui_state, -1),
+                        KeyCode::Down | KeyCode::Char('j') => select(&shown, &mut ui_state, 1),
+                        KeyCode::Left if ui_state.focus == Pane::Categories => {
+                            select(&shown, &mut ui_state, -1)
+                        }
+                        KeyCode::Right if ui_state.focus == Pane::Categories => {
+                            select(&shown, &mut ui_state, 1)
+                        }
+                        KeyCode::Enter => open_details(&shown, &mut ui_state),
+                        KeyCode::Char('?') => ui_state.show_help = true,
+                        KeyCode::Char('z') => ui_state.maximized ^= true,
+                        KeyCode::Char('v') => ui_state.layout = ui_state.layout.next(),
+                        KeyCode::Char(' ') => {
+                            ui_state.paused ^= true;
+                            frozen = ui_state.paused.then(|| Arc::new(app.snapshot()));
+                        }
+                        // A reset shows even while paused.
+                        KeyCode::Char('R') => {
+                            app.reset(ResetScope::All);
+                            frozen = ui_state.paused.then(|| Arc::new(app.snapshot()));
+                        }
+                        KeyCode::Char('C') => {
+                            app.reset(ResetScope::Category(ui_state.selected_category));
+                            frozen = ui_state.paused.then(|| Arc::new(app.snapshot()));
+                        }
+                        KeyCode::Char('M') => ui_state.marker_input = Some(String::new()),
+                        KeyCode::Char('l') => ui_state.show_latency ^= true,
+                        KeyCode::Char('e') => ui_state.show_log ^= true,
+                        KeyCode::Char('a') => ui_state.show_alerts ^= true,
+                        KeyCode::Char('h') => ui_state.show_history ^= true,
+                        KeyCode::Char('m') => ui_state.show_mix ^= true,
+                        KeyCode::Char('x') => ui_state.show_errors ^= true,
+                        KeyCode::Char('w') => write_report(Arc::clone(&app), report_dir, &ui_state),
+                        KeyCode::Char('t') => {
+                            ui_state.explorer = explore(&shown, ui_state.selected_server);
+                            ui_state.tree_cursor = 0;
+                        }
+                        KeyCode::Char('f') => {
+                            ui_state.filter_input = Some(app.filter.borrow().to_string())
+                        }
+                        KeyCode::Esc => {
+                            ui_state.show_members = false;
+                            ui_state.show_latency = false;
+                            ui_state.show_log = false;
+                            ui_state.show_alerts = false;
+                            ui_state.show_history = false;
+                            ui_state.show_mix = false;
+                            ui_state.show_errors = false;
+                            ui_state.explorer = None;
+                            ui_state.show_help = false;
+                        }
+                        _ => {}
+                    }
                 }
             }
         }
@@ -356,249 +893,426 @@ fn run_app<B: Backend>(
     }
 }
 
-fn ui(f: &mut Frame, app: Arc<App>) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(0)
-        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
-        .split(f.size());
+/// A key pressed while the filter is being edited.
+/// Enter applies the filter, if it parses, Esc leaves it as it was.
+fn edit_filter(app: &App, ui_state: &mut UiState, key: KeyCode) {
+    let Some(input) = ui_state.filter_input.as_mut() else {
+        return;
+    };
+
+    match key {
+        KeyCode::Char(c) => input.push(c),
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        KeyCode::Enter => match input.parse::<Filter>() {
+            Ok(filter) => {
+                app.filter.send_replace(filter);
+                ui_state.filter_input = None;
+                ui_state.filter_error = None;
+            }
+            Err(e) => ui_state.filter_error = Some(e),
+        },
+        KeyCode::Esc => {
+            ui_state.filter_input = None;
+            ui_state.filter_error = None;
+        }
+        _ => {}
+    }
+}
 
-    let bottom = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(33), Constraint::Percentage(67)].as_ref())
-        .split(chunks[1]);
+// Entries scrolled by PageUp and PageDown in the event log.
+const LOG_PAGE: i64 = 10;
 
-    let bottom_left = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(25), Constraint::Percentage(75)].as_ref())
-        .split(bottom[0]);
+// Entries scrolled by a notch of the mouse wheel in the event log.
+const WHEEL_LINES: i64 = 3;
 
-    let bottom_right = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-        .split(bottom[1]);
+/// A key pressed while a marker is being named.
+/// Enter places the marker, a marker without a name is numbered.
+fn edit_marker(app: &App, ui_state: &mut UiState, key: KeyCode) {
+    let Some(input) = ui_state.marker_input.as_mut() else {
+        return;
+    };
+
+    match key {
+        KeyCode::Char(c) => input.push(c),
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        KeyCode::Enter => {
+            let name = match input.trim() {
+                "" => format!("mark {}", app.markers.lock().unwrap().len() + 1),
+                name => name.to_string(),
+            };
+            app.mark(&name);
+            ui_state.marker_input = None;
+        }
+        KeyCode::Esc => ui_state.marker_input = None,
+        _ => {}
+    }
+}
+
+/// A key pressed while the event log is shown, returns whether it was meant for the log.
+fn on_log_key(app: &App, ui_state: &mut UiState, key: KeyCode) -> bool {
+    // Typing a search jumps to the newest match as it goes.
+    if ui_state.log_searching {
+        match key {
+            KeyCode::Char(c) => ui_state.log_search.push(c),
+            KeyCode::Backspace => {
+                ui_state.log_search.pop();
+            }
+            KeyCode::Enter => ui_state.log_searching = false,
+            KeyCode::Esc => {
+                ui_state.log_searching = false;
+                ui_state.log_search.clear();
+                ui_state.log_match = None;
+            }
+            _ => {}
+        }
+        if ui_state.log_searching {
+            search_log(app, ui_state, 0);
+        }
+        return true;
+    }
 
-    let tick_data = app.tick_data.lock().unwrap();
+    match key {
+        KeyCode::Char('/') => {
+            ui_state.log_searching = true;
+            ui_state.log_search.clear();
+            ui_state.log_match = None;
+        }
+        KeyCode::Char('n') => search_log(app, ui_state, -1),
+        KeyCode::Char('N') => search_log(app, ui_state, 1),
+        KeyCode::Char('p') | KeyCode::Char(' ') => {
+            ui_state.log_anchor = match ui_state.log_anchor {
+                Some(_) => None,
+                None => app.event_log.bounds().map(|(_, newest)| newest),
+            }
+        }
+        KeyCode::Up | KeyCode::Char('k') => scroll_log(app, ui_state, -1),
+        KeyCode::Down | KeyCode::Char('j') => scroll_log(app, ui_state, 1),
+        KeyCode::PageUp => scroll_log(app, ui_state, -LOG_PAGE),
+        KeyCode::PageDown => scroll_log(app, ui_state, LOG_PAGE),
+        KeyCode::Home => ui_state.log_anchor = app.event_log.bounds().map(|(oldest, _)| oldest),
+        KeyCode::End => ui_state.log_anchor = None,
+        _ => return false,
+    }
+
+    true
+}
+
+/// Move the bottom of the event log `delta` entries, newer if positive.
+/// Scrolling past the newest entry follows the log again.
+fn scroll_log(app: &App, ui_state: &mut UiState, delta: i64) {
+    let Some((oldest, newest)) = app.event_log.bounds() else {
+        return;
+    };
+
+    let at = ui_state.log_anchor.unwrap_or(newest).max(oldest) as i64 + delta;
+    ui_state.log_anchor = if at >= newest as i64 {
+        None
+    } else {
+        Some(at.max(oldest as i64) as u64)
+    };
+}
+
+/// Jump to the match of the search nearest to the current one,
+/// an older match if `step` is negative, a newer one if positive.
+fn search_log(app: &App, ui_state: &mut UiState, step: i64) {
+    let Some((_, newest)) = app.event_log.bounds() else {
+        return;
+    };
+    if ui_state.log_search.is_empty() {
+        ui_state.log_match = None;
+        return;
+    }
+
+    let current = ui_state
+        .log_match
+        .filter(|_| step != 0)
+        .or(ui_state.log_anchor)
+        .unwrap_or(newest);
+    let Some(from) = current.checked_add_signed(step) else {
+        return;
+    };
+
+    match app.event_log.find(&ui_state.log_search, from, step > 0) {
+        Some(seq) => {
+            ui_state.log_match = Some(seq);
+            ui_state.log_anchor = Some(seq);
+        }
+        // While typing, no match is news. Stepping past the last match is not.
+        None if step == 0 => ui_state.log_match = None,
+        None => {}
+    }
+}
 
-    let sparkline = Sparkline::default()
-        .block(
-            Block::default()
-                .title("AT-SPI2 signal monitor")
-                .border_style(Style::default().fg(Color::LightBlue))
-                .border_type(ratatui::widgets::BorderType::Rounded)
-                .borders(Borders::ALL),
+/// Explore the accessible tree of the server at `index` in the server list.
+/// A server that is being pinged has to be asked again.
+fn explore(app: &App, index: usize) -> Option<Explorer> {
+    let servers = app.servers.live().into_iter().chain(app.servers.gone());
+    let server = servers.into_iter().nth(index)?;
+    let guard = server.try_lock().ok()?;
+
+    Some(Explorer::open(
+        guard.accessible_proxy.connection().clone(),
+        guard.bus_name.as_str(),
+        &guard.accessible_name,
+    ))
+}
+
+/// Move the selection in the focused pane by `step` rows.
+fn select(app: &App, ui_state: &mut UiState, step: isize) {
+    let rows = match ui_state.focus {
+        Pane::Servers => app.servers.live().len() + app.servers.gone().len(),
+        Pane::Talkers => app.servers.talkers().len() + 1,
+        Pane::Errors => app.errors.sorted(ui_state.error_sort).len(),
+        Pane::Categories => {
+            let category = ui_state.selected_category;
+            ui_state.selected_category = if step < 0 {
+                category.prev()
+            } else {
+                category.next()
+            };
+            return;
+        }
+    };
+
+    let cursor = match ui_state.focus {
+        Pane::Servers => &mut ui_state.selected_server,
+        Pane::Talkers => &mut ui_state.talker_cursor,
+        _ => &mut ui_state.error_cursor,
+    };
+    *cursor = (*cursor)
+        .min(rows.saturating_sub(1))
+        .saturating_add_signed(step)
+        .min(rows.saturating_sub(1));
+}
+
+/// Open the details of what is selected in the focused pane.
+fn open_details(app: &App, ui_state: &mut UiState) {
+    match ui_state.focus {
+        Pane::Servers => ui_state.show_latency = true,
+        Pane::Categories => ui_state.show_members = true,
+        Pane::Errors => {
+            ui_state.show_errors = true;
+            ui_state.error_expanded = true;
+        }
+        Pane::Talkers => {
+            let bus_names = talker_bus_names(app, ui_state);
+            let talker = ui_state
+                .talker_cursor
+                .min(bus_names.len().saturating_sub(1));
+            let index = bus_names
+                .get(talker)
+                .and_then(|bus_name| server_index(app, bus_name));
+            if let Some(index) = index {
+                ui_state.explorer = explore(app, index);
+                ui_state.tree_cursor = 0;
+            }
+        }
+    }
+}
+
+// Position of the server at `bus_name` in the server list.
+fn server_index(app: &App, bus_name: &str) -> Option<usize> {
+    let servers = app.servers.live().into_iter().chain(app.servers.gone());
+    servers.into_iter().position(|server| {
+        server
+            .try_lock()
+            .is_ok_and(|guard| guard.bus_name.as_str() == bus_name)
+    })
+}
+
+/// A key pressed while the tree explorer is shown, returns whether it was meant for it.
+fn on_tree_key(ui_state: &mut UiState, key: KeyCode) -> bool {
+    let Some(explorer) = ui_state.explorer.as_ref() else {
+        return false;
+    };
+    let rows = explorer.rows();
+    let cursor = ui_state.tree_cursor.min(rows.len() - 1);
+    let node = rows[cursor].node;
+
+    ui_state.tree_cursor = match key {
+        KeyCode::Up | KeyCode::Char('k') => cursor.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => (cursor + 1).min(rows.len() - 1),
+        KeyCode::Right | KeyCode::Enter => {
+            if !explorer.is_expanded(node) {
+                explorer.expand(node);
+                cursor
+            } else if rows
+                .get(cursor + 1)
+                .is_some_and(|row| explorer.parent(row.node) == Some(node))
+            {
+                // Already open, go to the first child.
+                cursor + 1
+            } else {
+                cursor
+            }
+        }
+        KeyCode::Left => {
+            if explorer.is_expanded(node) {
+                explorer.collapse(node);
+                cursor
+            } else {
+                // Already closed, go to the parent.
+                explorer
+                    .parent(node)
+                    .and_then(|parent| rows.iter().position(|row| row.node == parent))
+                    .unwrap_or(cursor)
+            }
+        }
+        _ => return false,
+    };
+
+    true
+}
+
+/// A key pressed while the error details are shown. Returns whether the key was taken.
+fn on_errors_key(app: &App, ui_state: &mut UiState, key: KeyCode) -> bool {
+    let last = app
+        .errors
+        .sorted(ui_state.error_sort)
+        .len()
+        .saturating_sub(1);
+    let cursor = ui_state.error_cursor.min(last);
+
+    match key {
+        KeyCode::Up | KeyCode::Char('k') => ui_state.error_cursor = cursor.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => ui_state.error_cursor = (cursor + 1).min(last),
+        KeyCode::Enter => ui_state.error_expanded ^= true,
+        KeyCode::Char('o') => ui_state.error_sort = ui_state.error_sort.next(),
+        _ => return false,
+    }
+
+    true
+}
+
+/// A key pressed while the members, latency, alerts, history or mix are shown.
+/// Esc or the key that opened it closes the pop-up, the keys it has no use for are taken too,
+/// so they do not act on the dashboard behind it. Pausing and the help still work.
+/// Returns false if none of these pop-ups is shown.
+fn on_view_key(ui_state: &mut UiState, key: KeyCode) -> bool {
+    if !(ui_state.show_members
+        || ui_state.show_latency
+        || ui_state.show_alerts
+        || ui_state.show_history
+        || ui_state.show_mix)
+    {
+        return false;
+    }
+
+    match key {
+        KeyCode::Char(' ' | '?') => return false,
+        KeyCode::Esc => {
+            ui_state.show_members = false;
+            ui_state.show_latency = false;
+            ui_state.show_alerts = false;
+            ui_state.show_history = false;
+            ui_state.show_mix = false;
+        }
+        KeyCode::Enter => {
+            ui_state.show_members = false;
+            ui_state.show_latency = false;
+        }
+        KeyCode::Char('l') => ui_state.show_latency = false,
+        KeyCode::Char('a') => ui_state.show_alerts = false,
+        KeyCode::Char('h') => ui_state.show_history = false,
+        KeyCode::Char('m') => ui_state.show_mix = false,
+        KeyCode::Left if ui_state.show_members => {
+            ui_state.selected_category = ui_state.selected_category.prev()
+        }
+        KeyCode::Right | KeyCode::Char('c') if ui_state.show_members => {
+            ui_state.selected_category = ui_state.selected_category.next()
+        }
+        KeyCode::Char('+') if ui_state.show_history => {
+            ui_state.history_zoom = ui_state.history_zoom.zoom_in()
+        }
+        KeyCode::Char('-') if ui_state.show_history => {
+            ui_state.history_zoom = ui_state.history_zoom.zoom_out()
+        }
+        _ => {}
+    }
+
+    true
+}
+
+/// A mouse event. The wheel scrolls the pop-up shown or the list under the pointer,
+/// a click focuses the pane under the pointer and selects what was clicked in it.
+/// Returns false for the events it ignores, such as the pointer moving.
+fn on_mouse(app: &App, ui_state: &mut UiState, mouse: MouseEvent) -> bool {
+    let step = match mouse.kind {
+        MouseEventKind::ScrollUp => -1,
+        MouseEventKind::ScrollDown => 1,
+        MouseEventKind::Down(MouseButton::Left) => 0,
+        _ => return false,
+    };
+    let key = if step < 0 { KeyCode::Up } else { KeyCode::Down };
+
+    // The pop-ups take the wheel in the order they take the keys.
+    if ui_state.filter_input.is_some() || ui_state.marker_input.is_some() {
+        return true;
+    }
+    if ui_state.explorer.is_some() {
+        if step != 0 {
+            on_tree_key(ui_state, key);
+        }
+        return true;
+    }
+    if ui_state.show_log {
+        scroll_log(app, ui_state, step as i64 * WHEEL_LINES);
+        return true;
+    }
+    if ui_state.show_errors {
+        if step != 0 {
+            on_errors_key(app, ui_state, key);
+        }
+        return true;
+    }
+    if ui_state.shows_popup() {
+        return true;
+    }
+
+    let (pane, item, category) = {
+        let hits = ui_state.hits.borrow();
+        let Some(pane) = hits.pane_at(mouse.column, mouse.row) else {
+            return true;
+        };
+        (
+            pane,
+            hits.item_at(pane, mouse.row),
+            hits.category_at(mouse.column, mouse.row),
         )
-        .data(tick_data.as_slice())
-        .style(Style::default().fg(Color::Yellow));
-
-    // Rates: current, max, mean, total
-    let rate = Cell::from(app.rt_stats.rate.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-    let max = Cell::from(app.rt_stats.max.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-    let mean = Cell::from(app.rt_stats.mean.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-    let total = Cell::from(app.tally.total.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightMagenta)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD | ratatui::style::Modifier::UNDERLINED),
-    );
-
-    let keyboard = Cell::from(app.tally.keyboard.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let mouse = Cell::from(app.tally.mouse.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let focus = Cell::from(app.tally.focus.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let window = Cell::from(app.tally.window.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let object = Cell::from(app.tally.object.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let document = Cell::from(app.tally.document.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let terminal = Cell::from(app.tally.terminal.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let available = Cell::from(app.tally.available.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightGreen)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let listeners = Cell::from(app.tally.listeners.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightGreen)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let cache = Cell::from(app.tally.cache.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightBlue)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let other_event = Cell::from(app.tally.other_event.load().to_string()).style(
-        Style::default()
-            .fg(Color::LightRed)
-            .bg(Color::Black)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let error = Cell::from(app.tally.error.load().to_string()).style(
-        Style::default()
-            .fg(Color::White)
-            .bg(Color::Red)
-            .add_modifier(ratatui::style::Modifier::BOLD),
-    );
-
-    let column_data = [rate, max, mean, total];
-    let event_col1 = [keyboard, mouse, focus, window];
-    let event_col2 = [object, document, terminal, cache];
-    let event_col3 = [available, listeners, other_event, error];
-
-    let rates = Table::new([
-        Row::new(["Last", "Peak", "Mean", "Total"]).style(Style::default().fg(Color::LightYellow)),
-        Row::new(column_data).bottom_margin(2),
-    ])
-    .style(Style::default().fg(Color::LightYellow))
-    .widths(&[
-        Constraint::Length(10),
-        Constraint::Length(10),
-        Constraint::Length(10),
-        Constraint::Length(10),
-    ])
-    .column_spacing(1)
-    .block(
-        Block::default()
-            .title("AT-SPI2 signal rate dashboard:")
-            .border_style(Style::default().fg(Color::LightYellow))
-            .border_type(ratatui::widgets::BorderType::Rounded)
-            .borders(Borders::ALL),
-    );
-
-    let categories = Table::new([
-        Row::new(["Keyboard", "Focus", "Mouse", "Window"])
-            .style(Style::default().fg(Color::LightYellow)),
-        Row::new(event_col1).bottom_margin(1),
-        Row::new(["Object", "Document", "Terminal", "Cache"])
-            .style(Style::default().fg(Color::LightYellow)),
-        Row::new(event_col2).bottom_margin(1),
-        Row::new(["Available", "Listeners", "Other", "Error"])
-            .style(Style::default().fg(Color::LightYellow)),
-        Row::new(event_col3),
-    ])
-    .style(Style::default().fg(Color::LightYellow))
-    .widths(&[
-        Constraint::Length(8),
-        Constraint::Length(8),
-        Constraint::Length(8),
-        Constraint::Length(8),
-    ])
-    .column_spacing(1)
-    .block(
-        Block::default()
-            .title("Categorized signals")
-            .border_style(Style::default().fg(Color::LightYellow))
-            .border_type(ratatui::widgets::BorderType::Rounded)
-            .borders(Borders::ALL),
-    );
-
-    let binding = app.error_set.lock().unwrap();
-    let error_list = ratatui::widgets::List::new(
-        binding
-            .iter()
-            .map(|errs| ListItem::new(errs.as_str()))
-            .collect::<Vec<ListItem<'_>>>(),
-    )
-    .block(
-        Block::default()
-            .title("Errors")
-            .border_style(Style::default().fg(Color::LightRed))
-            .border_type(ratatui::widgets::BorderType::Rounded)
-            .borders(Borders::ALL),
-    )
-    .style(Style::default().fg(Color::LightRed))
-    .highlight_style(Style::default().fg(Color::Red))
-    .highlight_symbol(">> ");
-
-    let server_stats = &app.servers.bus;
-
-    let server_list = ratatui::widgets::List::new(
-        server_stats
-            .iter()
-            .map(|server| {
-                if let Ok(guard) = server.try_lock() {
-                    ListItem::new(format!("{}:\n\t{}\n", guard.accessible_name, guard.stats))
-                } else {
-                    ListItem::new(format!("Server contended for lock"))
-                }
-            })
-            .collect::<Vec<ListItem<'_>>>(),
-    )
-    .block(
-        Block::default()
-            .title("Server response time stats")
-            .border_style(Style::default().fg(Color::LightBlue))
-            .border_type(ratatui::widgets::BorderType::Rounded)
-            .borders(Borders::ALL),
-    )
-    .style(Style::default().fg(Color::LightYellow))
-    .highlight_style(Style::default().fg(Color::Blue))
-    .highlight_symbol(">> ");
-
-    f.render_widget(sparkline, chunks[0]);
-    f.render_widget(rates, bottom_left[0]);
-    f.render_widget(categories, bottom_left[1]);
-    f.render_widget(error_list, bottom_right[0]);
-    f.render_widget(server_list, bottom_right[1]);
+    };
+    ui_state.focus = pane;
+    if step != 0 {
+        select(app, ui_state, step);
+        return true;
+    }
+
+    match (pane, item, category) {
+        (Pane::Categories, _, Some(category)) => ui_state.selected_category = category,
+        (Pane::Servers, Some(item), _) => ui_state.selected_server = item,
+        (Pane::Talkers, Some(item), _) => ui_state.talker_cursor = item,
+        (Pane::Errors, Some(item), _) => ui_state.error_cursor = item,
+        _ => {}
+    }
+
+    true
+}
+
+/// Write a session report in the background, telling how it went in the status.
+fn write_report(app: Arc<App>, dir: &std::path::Path, ui_state: &UiState) {
+    let dir = dir.to_path_buf();
+    let status = Arc::clone(&ui_state.report_status);
+    *status.lock().unwrap() = Some("writing report".to_string());
+
+    tokio::spawn(async move {
+        let report = Report::take(&app).await;
+        let outcome = match report.write(&dir) {
+            Ok(path) => format!("report written to {}", path.display()),
+            Err(e) => format!("cannot write report: {e}"),
+        };
+        *status.lock().unwrap() = Some(outcome);
+    });
 }
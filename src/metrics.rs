@@ -0,0 +1,410 @@
+use crate::{
+    bus::PERCENTILES,
+    headless::{ServerSnapshot, Snapshot},
+    App,
+};
+use std::{
+    fmt::Write,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+
+// Requests are small, anything bigger is not a scrape.
+const MAX_REQUEST: usize = 8192;
+
+// Time a client gets to send its whole request, so a silent one does not hold a connection open.
+const REQUEST_DEADLINE: Duration = Duration::from_secs(5);
+
+/// Serve the metrics on `GET /metrics` in the Prometheus text format.
+pub async fn serve(listener: TcpListener, app: Arc<App>) {
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                tracing::error!("Metrics endpoint stopped: {e}");
+                return;
+            }
+        };
+
+        let app = Arc::clone(&app);
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &app).await {
+                tracing::debug!("Metrics request failed: {e}");
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, app: &App) -> std::io::Result<()> {
+    let request = match timeout(REQUEST_DEADLINE, read_request(&mut stream)).await {
+        Ok(request) => request?,
+        Err(_) => return Err(std::io::ErrorKind::TimedOut.into()),
+    };
+    let Some(request) = request else {
+        return Ok(());
+    };
+
+    let (status, body) = if request.starts_with(b"GET /metrics ") {
+        ("200 OK", render(&Snapshot::take(app).await, app.started))
+    } else {
+        ("404 Not Found", "Not found, try /metrics\n".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+// The request up to the end of its headers, `None` if the client gave up or sent too much.
+async fn read_request(stream: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Option<Vec<u8>>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || request.len() + n > MAX_REQUEST {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    Ok(Some(request))
+}
+
+// Label values escape backslashes, double quotes and newlines.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+fn server_labels(server: &ServerSnapshot) -> String {
+    format!(
+        "server=\"{}\",bus_name=\"{}\"",
+        escape(&server.name),
+        escape(&server.bus_name)
+    )
+}
+
+fn us_to_secs(us: u128) -> f64 {
+    us as f64 / 1_000_000.0
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// The snapshot of the session `started` at, in the Prometheus text exposition format.
+pub fn render(snapshot: &Snapshot, started: SystemTime) -> String {
+    let mut out = String::new();
+
+    header(
+        &mut out,
+        "statspi_start_time_seconds",
+        "gauge",
+        "Start time of statspi since the Unix epoch, in seconds.",
+    );
+    let started = started
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let _ = writeln!(out, "statspi_start_time_seconds {}", started.as_secs_f64());
+
+    header(
+        &mut out,
+        "statspi_events_total",
+        "counter",
+        "Events received, per category.",
+    );
+    for (category, count) in snapshot.categories.iter() {
+        let _ = writeln!(
+            out,
+            "statspi_events_total{{category=\"{}\"}} {count}",
+            category.to_lowercase()
+        );
+    }
+
+    header(
+        &mut out,
+        "statspi_events_filtered_total",
+        "counter",
+        "Events the filter kept out.",
+    );
+    let _ = writeln!(out, "statspi_events_filtered_total {}", snapshot.filtered);
+
+    for (name, value, help) in [
+        (
+            "statspi_event_rate",
+            snapshot.rate,
+            "Events in the last second.",
+        ),
+        (
+            "statspi_event_rate_peak",
+            snapshot.peak,
+            "Most events in a second.",
+        ),
+        (
+            "statspi_event_rate_mean",
+            snapshot.mean,
+            "Mean events per second.",
+        ),
+    ] {
+        header(&mut out, name, "gauge", help);
+        let _ = writeln!(out, "{name} {value}");
+    }
+
+    header(
+        &mut out,
+        "statspi_event_rate_window_mean",
+        "gauge",
+        "Mean events per second over the last window seconds.",
+    );
+    for window in snapshot.windows.iter() {
+        let _ = writeln!(
+            out,
+            "statspi_event_rate_window_mean{{window=\"{}\"}} {}",
+            window.secs, window.mean
+        );
+    }
+
+    header(
+        &mut out,
+        "statspi_event_rate_window_peak",
+        "gauge",
+        "Most events in a second over the last window seconds.",
+    );
+    for window in snapshot.windows.iter() {
+        let _ = writeln!(
+            out,
+            "statspi_event_rate_window_peak{{window=\"{}\"}} {}",
+            window.secs, window.peak
+        );
+    }
+
+    header(
+        &mut out,
+        "statspi_server_rtt_seconds",
+        "summary",
+        "Response times of the bus servers to pings.",
+    );
+    for server in snapshot.servers.iter() {
+        let labels = server_labels(server);
+        for ((_, q), (_, value)) in PERCENTILES.iter().zip(server.percentiles_us.iter()) {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    out,
+                    "statspi_server_rtt_seconds{{{labels},quantile=\"{q}\"}} {}",
+                    us_to_secs(*value)
+                );
+            }
+        }
+        let _ = writeln!(
+            out,
+            "statspi_server_rtt_seconds_sum{{{labels}}} {}",
+            us_to_secs(server.sum_us)
+        );
+        let _ = writeln!(
+            out,
+            "statspi_server_rtt_seconds_count{{{labels}}} {}",
+            server.samples
+        );
+    }
+
+    server_gauge(
+        &mut out,
+        snapshot,
+        "statspi_server_rtt_min_seconds",
+        "Fastest response of a bus server.",
+        |server| server.min_us.map(us_to_secs),
+    );
+    server_gauge(
+        &mut out,
+        snapshot,
+        "statspi_server_rtt_max_seconds",
+        "Slowest response of a bus server.",
+        |server| server.max_us.map(us_to_secs),
+    );
+    server_gauge(
+        &mut out,
+        snapshot,
+        "statspi_server_rtt_stddev_seconds",
+        "Standard deviation of the responses of a bus server.",
+        |server| server.std_dev_us.map(us_to_secs),
+    );
+
+    header(
+        &mut out,
+        "statspi_server_ping_timeouts_total",
+        "counter",
+        "Pings a bus server did not answer in time.",
+    );
+    for server in snapshot.servers.iter() {
+        let labels = server_labels(server);
+        let _ = writeln!(
+            out,
+            "statspi_server_ping_timeouts_total{{{labels}}} {}",
+            server.timeouts
+        );
+    }
+
+    header(
+        &mut out,
+        "statspi_server_ping_errors_total",
+        "counter",
+        "Pings a bus server answered with an error, per D-Bus error name.",
+    );
+    for server in snapshot.servers.iter() {
+        let labels = server_labels(server);
+        for (error, count) in server.errors.iter() {
+            let _ = writeln!(
+                out,
+                "statspi_server_ping_errors_total{{{labels},error=\"{}\"}} {count}",
+                escape(error)
+            );
+        }
+    }
+
+    server_gauge(
+        &mut out,
+        snapshot,
+        "statspi_server_availability_ratio",
+        "Fraction of the pings a bus server answered.",
+        |server| server.availability.map(|availability| availability / 100.0),
+    );
+    server_gauge(
+        &mut out,
+        snapshot,
+        "statspi_server_tree_nodes",
+        "Nodes in the accessible tree of a bus server, as last crawled.",
+        |server| server.tree.as_ref().map(|tree| tree.nodes as f64),
+    );
+    server_gauge(
+        &mut out,
+        snapshot,
+        "statspi_server_tree_depth",
+        "Depth of the accessible tree of a bus server, as last crawled.",
+        |server| server.tree.as_ref().map(|tree| tree.max_depth as f64),
+    );
+    server_gauge(
+        &mut out,
+        snapshot,
+        "statspi_server_departed",
+        "Whether a bus server has left the bus.",
+        |server| Some(f64::from(u8::from(server.departed))),
+    );
+
+    out
+}
+
+// A gauge with a sample per bus server, servers without a value are left out.
+fn server_gauge(
+    out: &mut String,
+    snapshot: &Snapshot,
+    name: &str,
+    help: &str,
+    value: impl Fn(&ServerSnapshot) -> Option<f64>,
+) {
+    header(out, name, "gauge", help);
+    for server in snapshot.servers.iter() {
+        if let Some(value) = value(server) {
+            let _ = writeln!(out, "{name}{{{}}} {value}", server_labels(server));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bus::Servers, category::Category, cli::Config, filter::Filter};
+    use std::collections::BTreeMap;
+
+    fn server(name: &str, bus_name: &str) -> ServerSnapshot {
+        ServerSnapshot {
+            name: name.to_string(),
+            bus_name: bus_name.to_string(),
+            samples: 4,
+            sum_us: 10_000,
+            min_us: Some(1_000),
+            max_us: Some(4_000),
+            mean_us: Some(2_500),
+            std_dev_us: Some(1_118),
+            percentiles_us: vec![("p50", Some(2_000)), ("p90", Some(4_000))],
+            timeouts: 1,
+            errors: BTreeMap::from([("org.freedesktop.DBus.Error.NoReply".to_string(), 2)]),
+            availability: Some(50.0),
+            last_responsive_secs_ago: Some(0),
+            departed: false,
+            tree: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn exposition_of_a_known_state() {
+        let app = App::new(
+            Servers::replayed(&[]),
+            &Config::default(),
+            Filter::default(),
+        );
+        app.tally.counter(Category::Object).add(7);
+        app.tally.counter(Category::Error).add(2);
+        app.tally.filtered.add(3);
+        app.rt_stats.rate.set(9);
+        app.rt_stats.max.set(12);
+
+        let mut snapshot = Snapshot::take(&app).await;
+        snapshot.servers.push(server("gedit \"2\"", ":1.5"));
+        let started = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let text = render(&snapshot, started);
+
+        for line in [
+            "# TYPE statspi_start_time_seconds gauge",
+            "statspi_start_time_seconds 1700000000",
+            "# TYPE statspi_events_total counter",
+            "statspi_events_total{category=\"object\"} 7",
+            "statspi_events_total{category=\"error\"} 2",
+            "statspi_events_total{category=\"mouse\"} 0",
+            "statspi_events_filtered_total 3",
+            "statspi_event_rate 9",
+            "statspi_event_rate_peak 12",
+            "statspi_server_rtt_seconds{server=\"gedit \\\"2\\\"\",bus_name=\":1.5\",quantile=\"0.5\"} 0.002",
+            "statspi_server_rtt_seconds_sum{server=\"gedit \\\"2\\\"\",bus_name=\":1.5\"} 0.01",
+            "statspi_server_rtt_seconds_count{server=\"gedit \\\"2\\\"\",bus_name=\":1.5\"} 4",
+            "statspi_server_ping_timeouts_total{server=\"gedit \\\"2\\\"\",bus_name=\":1.5\"} 1",
+            "statspi_server_availability_ratio{server=\"gedit \\\"2\\\"\",bus_name=\":1.5\"} 0.5",
+        ] {
+            assert!(text.lines().any(|l| l == line), "no line {line} in\n{text}");
+        }
+
+        // Each sample is a name, maybe labels, and a number.
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let (name, value) = line.rsplit_once(' ').unwrap();
+            assert!(name.starts_with("statspi_"), "{line}");
+            assert!(value.parse::<f64>().is_ok(), "{line}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reading_requests() {
+        let request = b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let read = read_request(&mut &request[..]).await.unwrap();
+        assert_eq!(read.as_deref(), Some(&request[..]));
+
+        // The client went away before the end of the headers.
+        let cut = b"GET /metrics HTTP/1.1\r\nHost: loc";
+        assert_eq!(read_request(&mut &cut[..]).await.unwrap(), None);
+
+        // Too much to be a scrape.
+        let big = [b'a'; MAX_REQUEST + 1];
+        assert_eq!(read_request(&mut &big[..]).await.unwrap(), None);
+    }
+}
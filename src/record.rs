@@ -0,0 +1,252 @@
+use crate::{
+    bus::{Membership, Servers},
+    errors::{ErrorKind, ReplayedError},
+    signal::SignalHeader,
+    App, AtspiEvent, Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Lines, Write},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const RECORDING_VERSION: u32 = 1;
+
+// How often the recording is flushed to disk.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The first line of a recording: what is needed to make sense of the signals that follow.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub version: u32,
+    /// Start of the recording in seconds since the Unix epoch.
+    pub started: u64,
+    /// The bus servers at the start of the recording, as (bus name, accessible name).
+    pub servers: Vec<(String, String)>,
+}
+
+/// Every line after the header is a recorded signal.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedSignal {
+    /// Microseconds since the start of the recording.
+    pub t: u64,
+    #[serde(flatten)]
+    pub header: SignalHeader,
+    /// The event, its body included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<AtspiEvent>,
+    /// Why the signal could not be turned into an event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// What kind of error it was, recordings made before errors were classified lack this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ErrorKind>,
+}
+
+/// A server joining or leaving the bus during the recording.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerChange {
+    Joined {
+        bus_name: String,
+        accessible_name: String,
+    },
+    Left {
+        bus_name: String,
+    },
+}
+
+/// A change of the bus servers, on a line of its own between the signals.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedServerChange {
+    /// Microseconds since the start of the recording.
+    pub t: u64,
+    #[serde(flatten)]
+    pub change: ServerChange,
+}
+
+// A line after the header. Recordings made before servers could join lack the server changes.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RecordedLine {
+    Server(RecordedServerChange),
+    Signal(Box<RecordedSignal>),
+}
+
+/// Writes the received signals to a file, as JSON lines.
+pub struct Recorder {
+    out: BufWriter<File>,
+    start: Instant,
+    last_flush: Instant,
+    // Bus names of the servers the recording has named so far.
+    named: HashSet<String>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, servers: &Servers) -> Result<Recorder> {
+        let mut out = BufWriter::new(File::create(path)?);
+
+        let header = RecordingHeader {
+            version: RECORDING_VERSION,
+            started: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            servers: servers
+                .talkers()
+                .iter()
+                .map(|talker| (talker.bus_name.clone(), talker.accessible_name.clone()))
+                .collect(),
+        };
+        let named = header
+            .servers
+            .iter()
+            .map(|(bus_name, _)| bus_name.clone())
+            .collect();
+        serde_json::to_writer(&mut out, &header)?;
+        out.write_all(b"\n")?;
+
+        let now = Instant::now();
+        Ok(Recorder {
+            out,
+            start: now,
+            last_flush: now,
+            named,
+        })
+    }
+
+    /// Write a signal. A sender that joined the bus since the start is named before its
+    /// first signal that `servers` can attribute, a server leaving is noted after its last.
+    pub fn record(
+        &mut self,
+        header: Option<&SignalHeader>,
+        event: &Result<AtspiEvent>,
+        servers: &Servers,
+    ) -> Result<()> {
+        let sender = header.and_then(|header| header.sender.as_deref());
+        if let Some(sender) = sender.filter(|sender| !self.named.contains(*sender)) {
+            if let Some(accessible_name) = servers.accessible_name(sender) {
+                self.named.insert(sender.to_string());
+                self.write_change(ServerChange::Joined {
+                    bus_name: sender.to_string(),
+                    accessible_name,
+                })?;
+            }
+        }
+
+        let (event, error, error_kind) = match event {
+            Ok(event) => (Some(event.clone()), None, None),
+            Err(e) => (None, Some(e.to_string()), Some(ErrorKind::of(e.as_ref()))),
+        };
+
+        let signal = RecordedSignal {
+            t: self.start.elapsed().as_micros() as u64,
+            header: header.cloned().unwrap_or_default(),
+            event,
+            error,
+            error_kind,
+        };
+        serde_json::to_writer(&mut self.out, &signal)?;
+        self.out.write_all(b"\n")?;
+
+        if let Some(Membership::Left(bus_name)) = signal.event.as_ref().and_then(Membership::of) {
+            if self.named.remove(&bus_name) {
+                self.write_change(ServerChange::Left { bus_name })?;
+            }
+        }
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.out.flush()?;
+            self.last_flush = Instant::now();
+        }
+
+        Ok(())
+    }
+
+    fn write_change(&mut self, change: ServerChange) -> Result<()> {
+        let line = RecordedServerChange {
+            t: self.start.elapsed().as_micros() as u64,
+            change,
+        };
+        serde_json::to_writer(&mut self.out, &line)?;
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// Reads a recording back.
+pub struct Replay {
+    pub header: RecordingHeader,
+    lines: Lines<BufReader<File>>,
+}
+
+impl Replay {
+    pub fn open(path: &Path) -> Result<Replay> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+
+        let first = lines.next().ok_or("recording is empty")??;
+        let header: RecordingHeader = serde_json::from_str(&first)?;
+        if header.version != RECORDING_VERSION {
+            return Err(format!("unsupported recording version {}", header.version).into());
+        }
+
+        Ok(Replay { header, lines })
+    }
+
+    /// The bus servers as they were at the start of the recording.
+    pub fn servers(&self) -> Servers {
+        Servers::replayed(&self.header.servers)
+    }
+
+    /// Feed the recorded signals to `app`, `speed` times as fast as they were recorded.
+    pub async fn run(self, app: Arc<App>, speed: f64) {
+        let start = tokio::time::Instant::now();
+
+        for (n, line) in self.lines.enumerate() {
+            let line = match line.map_err(|e| e.to_string()).and_then(|line| {
+                serde_json::from_str::<RecordedLine>(&line).map_err(|e| e.to_string())
+            }) {
+                Ok(line) => line,
+                Err(e) => {
+                    // Line one is the header.
+                    tracing::error!("Replay stopped at line {}: {e}", n + 2);
+                    return;
+                }
+            };
+
+            let signal = match line {
+                RecordedLine::Signal(signal) => *signal,
+                RecordedLine::Server(server) => {
+                    let at = Duration::from_micros(server.t).div_f64(speed);
+                    tokio::time::sleep_until(start + at).await;
+                    match server.change {
+                        ServerChange::Joined {
+                            bus_name,
+                            accessible_name,
+                        } => app.servers.add_talker(&bus_name, &accessible_name),
+                        ServerChange::Left { bus_name } => {
+                            app.servers.remove_server(&bus_name).await
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            let at = Duration::from_micros(signal.t).div_f64(speed);
+            tokio::time::sleep_until(start + at).await;
+
+            let event = match (signal.event, signal.error) {
+                (Some(event), _) => Ok(event),
+                (None, error) => Err(ReplayedError {
+                    kind: signal.error_kind.unwrap_or(ErrorKind::Other),
+                    message: error.unwrap_or_default(),
+                }
+                .into()),
+            };
+            app.on_event(Some(&signal.header), event);
+        }
+
+        tracing::info!("Replay ended");
+    }
+}
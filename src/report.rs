@@ -0,0 +1,275 @@
+use crate::{
+    alert::Alert,
+    control::Marker,
+    errors::{ErrorEntry, ErrorSort},
+    headless::Snapshot,
+    history::{History, Resolution},
+    App, Result,
+};
+use serde::Serialize;
+use std::{
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::{BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// All statspi learned in a session, for attaching to a bug report.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Start of the session in seconds since the Unix epoch.
+    pub started: u64,
+    pub duration_secs: u64,
+    /// The filter in effect at the end of the session.
+    pub filter: String,
+    #[serde(flatten)]
+    pub snapshot: Snapshot,
+    /// Events per second, by the second, the minute and the hour.
+    pub history: History,
+    /// The errors met by kind and message, the most frequent first.
+    pub errors: Vec<ErrorEntry>,
+    /// Error groups pushed out to make room for new ones.
+    pub errors_evicted: u64,
+    /// The markers placed, the oldest first.
+    pub markers: Vec<Marker>,
+    /// The alerts that ended, the latest first. Those still active are in `alerts`.
+    pub alert_history: Vec<Alert>,
+}
+
+impl Report {
+    pub async fn take(app: &App) -> Report {
+        let since_epoch = |at: SystemTime| {
+            at.duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs())
+        };
+
+        // The servers are locked in turn, before anything else is.
+        let snapshot = Snapshot::take(app).await;
+
+        Report {
+            started: since_epoch(app.started),
+            duration_secs: app.started.elapsed().map_or(0, |elapsed| elapsed.as_secs()),
+            filter: app.filter.borrow().to_string(),
+            snapshot,
+            history: app.history.lock().unwrap().clone(),
+            errors: app.errors.sorted(ErrorSort::Count),
+            errors_evicted: app.errors.evicted(),
+            markers: app.markers.lock().unwrap().clone(),
+            alert_history: app.alerts.history(),
+        }
+    }
+
+    /// The report in long form: section, name, field and value on every row.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("section,name,field,value\n");
+        let mut row = |section: &str, name: &str, field: &str, value: &dyn std::fmt::Display| {
+            let _ = writeln!(
+                csv,
+                "{section},{},{},{}",
+                quoted(name),
+                quoted(field),
+                quoted(&value.to_string())
+            );
+        };
+
+        row("session", "", "started", &self.started);
+        row("session", "", "duration_secs", &self.duration_secs);
+        row("session", "", "filter", &self.filter);
+        row("session", "", "total", &self.snapshot.total);
+        row("session", "", "filtered", &self.snapshot.filtered);
+        row("session", "", "peak", &self.snapshot.peak);
+        row("session", "", "mean", &self.snapshot.mean);
+        for window in self.snapshot.windows.iter() {
+            let name = window.label();
+            row("window", &name, "mean", &format!("{:.1}", window.mean));
+            row("window", &name, "peak", &window.peak);
+        }
+
+        for (category, count) in self.snapshot.categories.iter() {
+            row("category", category, "events", count);
+        }
+
+        for (second, sample) in self.history.samples(Resolution::Second) {
+            row("second", &second.to_string(), "events", &sample.events);
+        }
+        for resolution in [Resolution::Minute, Resolution::Hour] {
+            for (second, sample) in self.history.samples(resolution) {
+                let name = second.to_string();
+                row(resolution.name(), &name, "events", &sample.events);
+                row(resolution.name(), &name, "peak", &sample.peak);
+            }
+        }
+
+        let us = |value: Option<u128>| value.map_or(String::new(), |v| v.to_string());
+        for server in self.snapshot.servers.iter() {
+            let name = format!("{} ({})", server.name, server.bus_name);
+            row("server", &name, "samples", &server.samples);
+            row("server", &name, "min_us", &us(server.min_us));
+            row("server", &name, "max_us", &us(server.max_us));
+            row("server", &name, "mean_us", &us(server.mean_us));
+            row("server", &name, "std_dev_us", &us(server.std_dev_us));
+            for (label, value) in server.percentiles_us.iter() {
+                row("server", &name, &format!("{label}_us"), &us(*value));
+            }
+            row("server", &name, "timeouts", &server.timeouts);
+            for (error, count) in server.errors.iter() {
+                row("server", &name, &format!("error {error}"), count);
+            }
+            let availability = server
+                .availability
+                .map_or(String::new(), |a| format!("{a:.1}"));
+            row("server", &name, "availability", &availability);
+            row("server", &name, "departed", &server.departed);
+            if let Some(tree) = &server.tree {
+                row("server", &name, "tree_nodes", &tree.nodes);
+                row("server", &name, "tree_depth", &tree.max_depth);
+                row("server", &name, "tree_fan_out", &tree.max_fan_out);
+            }
+        }
+
+        for marker in self.markers.iter() {
+            row("marker", &marker.name, "second", &marker.second);
+        }
+
+        for error in self.errors.iter() {
+            let name = format!("{}: {}", error.kind, error.message);
+            row("error", &name, "count", &error.count);
+            row(
+                "error",
+                &name,
+                "first_seen_secs",
+                &error.first_seen.as_secs(),
+            );
+            row("error", &name, "last_seen_secs", &error.last_seen.as_secs());
+        }
+        row("session", "", "errors_evicted", &self.errors_evicted);
+
+        csv
+    }
+
+    /// Write the report to `dir`, as JSON and as CSV. Returns the path of the JSON file.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let create = |path: &Path| OpenOptions::new().write(true).create_new(true).open(path);
+
+        // Reports written in the same second are told apart by a suffix, none is overwritten.
+        let mut n = 0;
+        let (json, csv, files) = loop {
+            let stem = match n {
+                0 => dir.join(format!("statspi-{now}")),
+                n => dir.join(format!("statspi-{now}-{n}")),
+            };
+            let (json, csv) = (stem.with_extension("json"), stem.with_extension("csv"));
+            n += 1;
+
+            let json_file = match create(&json) {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            };
+            match create(&csv) {
+                Ok(csv_file) => break (json, csv, (json_file, csv_file)),
+                Err(e) => {
+                    let _ = std::fs::remove_file(&json);
+                    if e.kind() != ErrorKind::AlreadyExists {
+                        return Err(e.into());
+                    }
+                }
+            }
+        };
+
+        // Half a report is no report.
+        if let Err(e) = self.write_to(files) {
+            let _ = std::fs::remove_file(&json);
+            let _ = std::fs::remove_file(&csv);
+            return Err(e);
+        }
+
+        Ok(json)
+    }
+
+    fn write_to(&self, (json, csv): (File, File)) -> Result<()> {
+        let mut out = BufWriter::new(json);
+        serde_json::to_writer_pretty(&mut out, self)?;
+        out.write_all(b"\n")?;
+        out.flush()?;
+
+        let mut out = BufWriter::new(csv);
+        out.write_all(self.to_csv().as_bytes())?;
+        out.flush()?;
+
+        Ok(())
+    }
+}
+
+// A CSV field, quoted if it has to be.
+fn quoted(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Where reports go unless asked otherwise: `$XDG_STATE_HOME/statspi`.
+pub fn default_dir() -> Option<PathBuf> {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))?;
+
+    Some(state_home.join("statspi"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bus::Servers, cli::Config, filter::Filter};
+
+    #[tokio::test]
+    async fn reports_never_overwrite() {
+        let dir = std::env::temp_dir().join(format!("statspi-reports-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let app = App::new(
+            Servers::replayed(&[]),
+            &Config::default(),
+            Filter::default(),
+        );
+        let report = Report::take(&app).await;
+
+        // A stray CSV where the report of this second would go, and of the next in case it turns.
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for second in [now, now + 1] {
+            std::fs::write(dir.join(format!("statspi-{second}.csv")), "stray").unwrap();
+        }
+
+        let written: Vec<PathBuf> = (0..3).map(|_| report.write(&dir).unwrap()).collect();
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        let strays = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .filter(|text| text == "stray")
+            .count();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(strays, 2);
+        // Each report is a JSON and a CSV of its own, no JSON is left without its CSV.
+        assert_eq!(names.len(), 2 + 2 * 3, "{names:?}");
+        for json in written {
+            let name = json.file_name().unwrap().to_str().unwrap();
+            assert!(names.iter().any(|other| other == name));
+            let csv = json.with_extension("csv");
+            assert!(names
+                .iter()
+                .any(|other| other == csv.file_name().unwrap().to_str().unwrap()));
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use atspi::events::{
+    signatures_are_eq, EventBodyOwned, EventBodyQT, ATSPI_EVENT_SIGNATURE, QSPI_EVENT_SIGNATURE,
+};
+use serde::{Deserialize, Serialize};
+use zbus::Message;
+
+/// The parts of a signal's message header statspi keeps track of.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalHeader {
+    /// Unique bus name of the sender, e.g. ":1.42".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// Interface of the signal, e.g. "org.a11y.atspi.Event.Object".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<String>,
+    /// Object path of the object the signal is about.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Signal member, e.g. "StateChanged".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member: Option<String>,
+    /// The event body's "kind", e.g. "focused" for a state change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl From<&Message> for SignalHeader {
+    fn from(msg: &Message) -> Self {
+        let sender = msg.header().ok().and_then(|header| {
+            header
+                .sender()
+                .ok()
+                .flatten()
+                .map(|sender| sender.to_string())
+        });
+        let interface = msg.interface().map(|interface| interface.to_string());
+        let path = msg.path().map(|path| path.to_string());
+        let member = msg.member().map(|member| member.to_string());
+
+        SignalHeader {
+            sender,
+            interface,
+            path,
+            member,
+            detail: detail(msg),
+        }
+    }
+}
+
+// Only AT-SPI event bodies carry a detail, Qt has its own signature for these.
+fn detail(msg: &Message) -> Option<String> {
+    let signature = msg.body_signature().ok()?;
+
+    let kind = if signatures_are_eq(&signature, &ATSPI_EVENT_SIGNATURE) {
+        msg.body::<EventBodyOwned>().ok()?.kind
+    } else if signatures_are_eq(&signature, &QSPI_EVENT_SIGNATURE) {
+        msg.body::<EventBodyQT>().ok()?.kind
+    } else {
+        return None;
+    };
+
+    (!kind.is_empty()).then_some(kind)
+}
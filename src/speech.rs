@@ -0,0 +1,271 @@
+use crate::headless::Snapshot;
+use std::collections::{BTreeMap, BTreeSet};
+
+// The rate is told again when it moved this much from what was last told, as a fraction.
+const RATE_CHANGE: f64 = 0.25;
+
+// Changes of fewer events per second than this go untold, they are noise.
+const RATE_NOISE: u64 = 5;
+
+// A server that did not answer for this many seconds is told to be unresponsive.
+const UNRESPONSIVE_SECS: u64 = 10;
+
+// What was last told, to tell only what changed.
+#[derive(Debug, Default)]
+struct Told {
+    rate: u64,
+    errors: u64,
+    alerts: BTreeSet<(&'static str, String)>,
+    // The servers by bus name, with whether they were responsive.
+    servers: BTreeMap<String, (String, bool)>,
+}
+
+/// Turns snapshots into short sentences for a screen reader, one per line.
+/// The first snapshot is told in full, after that only what changed.
+#[derive(Debug, Default)]
+pub struct Announcer {
+    told: Option<Told>,
+}
+
+impl Announcer {
+    pub fn announce(&mut self, snapshot: &Snapshot) -> Vec<String> {
+        let errors = snapshot
+            .categories
+            .iter()
+            .find(|(name, _)| *name == "Error")
+            .map_or(0, |(_, count)| *count);
+        let alerts: BTreeSet<(&'static str, String)> = snapshot
+            .alerts
+            .iter()
+            .map(|alert| (alert.rule.name(), spoken(&alert.message)))
+            .collect();
+        let servers: BTreeMap<String, (String, bool)> = snapshot
+            .servers
+            .iter()
+            .filter(|server| !server.departed)
+            .map(|server| {
+                // A server that never answered is unresponsive once a ping failed.
+                let responsive = match server.last_responsive_secs_ago {
+                    Some(secs) => secs < UNRESPONSIVE_SECS,
+                    None => server.timeouts == 0 && server.errors.is_empty(),
+                };
+                (server.bus_name.clone(), (server.name.clone(), responsive))
+            })
+            .collect();
+
+        let mut lines = Vec::new();
+        let Some(told) = self.told.as_ref() else {
+            lines.push(format!(
+                "{} events per second, {} in all.",
+                snapshot.rate, snapshot.total
+            ));
+            lines.push(match servers.len() {
+                1 => "1 application on the bus.".to_string(),
+                n => format!("{n} applications on the bus."),
+            });
+            match errors {
+                0 => {}
+                1 => lines.push("1 error.".to_string()),
+                n => lines.push(format!("{n} errors.")),
+            }
+            for (rule, message) in alerts.iter() {
+                lines.push(format!("Alert, {rule}: {message}."));
+            }
+            self.told = Some(Told {
+                rate: snapshot.rate,
+                errors,
+                alerts,
+                servers,
+            });
+            return lines;
+        };
+
+        let moved = snapshot.rate.abs_diff(told.rate);
+        let rate = if moved >= RATE_NOISE && moved as f64 > RATE_CHANGE * told.rate as f64 {
+            lines.push(format!("{} events per second.", snapshot.rate));
+            snapshot.rate
+        } else {
+            told.rate
+        };
+
+        for (rule, message) in alerts.difference(&told.alerts) {
+            lines.push(format!("Alert, {rule}: {message}."));
+        }
+        for (rule, _) in told.alerts.difference(&alerts) {
+            // An alert that changed its message is still on.
+            if !alerts.iter().any(|(active, _)| active == rule) {
+                lines.push(format!("Alert over, {rule}."));
+            }
+        }
+
+        for (bus_name, (name, responsive)) in servers.iter() {
+            match told.servers.get(bus_name) {
+                None => lines.push(format!("{name} joined.")),
+                Some((_, true)) if !responsive => lines.push(format!("{name} is not responding.")),
+                Some((_, false)) if *responsive => lines.push(format!("{name} responds again.")),
+                _ => {}
+            }
+        }
+        for (bus_name, (name, _)) in told.servers.iter() {
+            if !servers.contains_key(bus_name) {
+                lines.push(format!("{name} left."));
+            }
+        }
+
+        // Fewer errors than told means the counters were reset, all of them are new.
+        let new_errors = if errors < told.errors {
+            errors
+        } else {
+            errors - told.errors
+        };
+        match new_errors {
+            0 => {}
+            1 => lines.push("1 new error.".to_string()),
+            n => lines.push(format!("{n} new errors.")),
+        }
+
+        self.told = Some(Told {
+            rate,
+            errors,
+            alerts,
+            servers,
+        });
+        lines
+    }
+}
+
+// Symbols a screen reader may skip or spell out, in words.
+fn spoken(text: &str) -> String {
+    text.replace('×', " times").replace('%', " percent")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::ServerSnapshot;
+
+    fn server(
+        name: &str,
+        bus_name: &str,
+        last_responsive_secs_ago: Option<u64>,
+        timeouts: u32,
+    ) -> ServerSnapshot {
+        ServerSnapshot {
+            name: name.to_string(),
+            bus_name: bus_name.to_string(),
+            samples: 0,
+            sum_us: 0,
+            min_us: None,
+            max_us: None,
+            mean_us: None,
+            std_dev_us: None,
+            percentiles_us: Vec::new(),
+            timeouts,
+            errors: BTreeMap::new(),
+            availability: None,
+            last_responsive_secs_ago,
+            departed: false,
+            tree: None,
+        }
+    }
+
+    fn snapshot(rate: u64, total: u64, errors: u64, servers: Vec<ServerSnapshot>) -> Snapshot {
+        Snapshot {
+            seconds: 0,
+            rate,
+            peak: rate,
+            mean: rate,
+            total,
+            windows: Vec::new(),
+            filtered: 0,
+            categories: vec![("Object", total - errors), ("Error", errors)],
+            servers,
+            alerts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn the_first_snapshot_in_full() {
+        let mut announcer = Announcer::default();
+        let lines = announcer.announce(&snapshot(
+            40,
+            120,
+            1,
+            vec![server("gedit", ":1.5", Some(0), 0)],
+        ));
+        assert_eq!(
+            lines,
+            [
+                "40 events per second, 120 in all.",
+                "1 application on the bus.",
+                "1 error."
+            ]
+        );
+    }
+
+    #[test]
+    fn only_what_changed() {
+        let mut announcer = Announcer::default();
+        announcer.announce(&snapshot(
+            40,
+            120,
+            0,
+            vec![server("gedit", ":1.5", Some(0), 0)],
+        ));
+
+        // Too small a change of the rate to tell.
+        let lines = announcer.announce(&snapshot(
+            44,
+            160,
+            0,
+            vec![server("gedit", ":1.5", Some(1), 0)],
+        ));
+        assert!(lines.is_empty());
+
+        let lines = announcer.announce(&snapshot(
+            100,
+            260,
+            2,
+            vec![
+                server("gedit", ":1.5", Some(12), 3),
+                server("firefox", ":1.7", None, 0),
+            ],
+        ));
+        assert_eq!(
+            lines,
+            [
+                "100 events per second.",
+                "gedit is not responding.",
+                "firefox joined.",
+                "2 new errors."
+            ]
+        );
+
+        // A server that never answered is unresponsive once a ping failed.
+        let lines = announcer.announce(&snapshot(
+            100,
+            360,
+            2,
+            vec![
+                server("gedit", ":1.5", Some(0), 3),
+                server("firefox", ":1.7", None, 1),
+            ],
+        ));
+        assert_eq!(
+            lines,
+            ["gedit responds again.", "firefox is not responding."]
+        );
+    }
+
+    #[test]
+    fn errors_after_a_reset() {
+        let mut announcer = Announcer::default();
+        announcer.announce(&snapshot(40, 120, 10, Vec::new()));
+
+        let lines = announcer.announce(&snapshot(40, 3, 3, Vec::new()));
+        assert_eq!(lines, ["3 new errors."]);
+
+        let lines = announcer.announce(&snapshot(40, 5, 4, Vec::new()));
+        assert_eq!(lines, ["1 new error."]);
+    }
+}
@@ -0,0 +1,318 @@
+use crate::ACCESSIBLE_ROOT_PATH;
+use atspi::{proxy::accessible::AccessibleProxy, ObjectRef};
+use std::{
+    future::Future,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+use tokio::time::timeout;
+use zbus::{CacheProperties, Connection, ProxyBuilder};
+
+// Time an application gets to answer a question about one of its objects.
+const CALL_DEADLINE: Duration = Duration::from_secs(5);
+
+/// What an accessible object tells about itself.
+#[derive(Debug, Clone)]
+pub struct NodeInfo {
+    pub role: String,
+    pub name: String,
+    pub states: Vec<String>,
+    pub interfaces: Vec<String>,
+    pub child_count: i32,
+}
+
+impl NodeInfo {
+    async fn fetch(conn: &Connection, object: &ObjectRef) -> zbus::Result<NodeInfo> {
+        let proxy = accessible(conn, object).await?;
+
+        Ok(NodeInfo {
+            role: proxy.get_role().await?.name().to_string(),
+            name: proxy.name().await?,
+            states: proxy.get_state().await?.iter().map(String::from).collect(),
+            interfaces: proxy
+                .get_interfaces()
+                .await?
+                .iter()
+                .map(|interface| {
+                    let interface = interface.to_string();
+                    match interface.strip_prefix("org.a11y.atspi.") {
+                        Some(short) => short.to_string(),
+                        None => interface,
+                    }
+                })
+                .collect(),
+            child_count: proxy.child_count().await?,
+        })
+    }
+}
+
+#[derive(Debug)]
+enum Info {
+    Loading,
+    Loaded(NodeInfo),
+    Failed(String),
+}
+
+#[derive(Debug)]
+enum Children {
+    Unloaded,
+    Loading,
+    Loaded(Vec<usize>),
+    Failed(String),
+}
+
+#[derive(Debug)]
+struct Node {
+    object: ObjectRef,
+    parent: Option<usize>,
+    depth: usize,
+    info: Info,
+    children: Children,
+    expanded: bool,
+}
+
+impl Node {
+    fn new(object: ObjectRef, parent: Option<usize>, depth: usize) -> Node {
+        Node {
+            object,
+            parent,
+            depth,
+            info: Info::Loading,
+            children: Children::Unloaded,
+            expanded: false,
+        }
+    }
+}
+
+/// A line of the tree as it is shown.
+#[derive(Debug, Clone)]
+pub struct TreeRow {
+    pub node: usize,
+    pub text: String,
+}
+
+/// Browses the accessible tree of an application.
+/// Children are asked for when their parent is first expanded,
+/// the asking stops when the explorer is closed.
+#[derive(Debug, Clone)]
+pub struct Explorer {
+    pub app_name: String,
+    conn: Connection,
+    // The nodes met so far, the root first.
+    nodes: Arc<Mutex<Vec<Node>>>,
+}
+
+impl Explorer {
+    /// Explore the application at `bus_name`, from its root.
+    pub fn open(conn: Connection, bus_name: &str, app_name: &str) -> Explorer {
+        let root = ObjectRef {
+            name: bus_name.to_string(),
+            path: ACCESSIBLE_ROOT_PATH.into(),
+        };
+
+        let explorer = Explorer {
+            app_name: app_name.to_string(),
+            conn,
+            nodes: Arc::new(Mutex::new(vec![Node::new(root, None, 0)])),
+        };
+
+        let (conn, nodes) = (explorer.conn.clone(), Arc::downgrade(&explorer.nodes));
+        tokio::spawn(async move { load_info(&conn, &nodes, 0).await });
+        explorer.expand(0);
+
+        explorer
+    }
+
+    /// Show the children of `node`, asking for them if that was not done yet.
+    pub fn expand(&self, node: usize) {
+        let mut nodes = self.nodes.lock().unwrap();
+        let Some(expanded) = nodes.get_mut(node) else {
+            return;
+        };
+        expanded.expanded = true;
+        if !matches!(expanded.children, Children::Unloaded) {
+            return;
+        }
+        expanded.children = Children::Loading;
+
+        let (conn, nodes) = (self.conn.clone(), Arc::downgrade(&self.nodes));
+        tokio::spawn(async move { load_children(&conn, &nodes, node).await });
+    }
+
+    pub fn collapse(&self, node: usize) {
+        if let Some(node) = self.nodes.lock().unwrap().get_mut(node) {
+            node.expanded = false;
+        }
+    }
+
+    pub fn is_expanded(&self, node: usize) -> bool {
+        self.nodes
+            .lock()
+            .unwrap()
+            .get(node)
+            .is_some_and(|node| node.expanded)
+    }
+
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.nodes.lock().unwrap().get(node)?.parent
+    }
+
+    /// The nodes shown: the root and the descendants of expanded nodes, depth first.
+    pub fn rows(&self) -> Vec<TreeRow> {
+        let nodes = self.nodes.lock().unwrap();
+        let mut rows = Vec::new();
+        let mut stack = vec![0];
+
+        while let Some(id) = stack.pop() {
+            let node = &nodes[id];
+            rows.push(TreeRow {
+                node: id,
+                text: row_text(node),
+            });
+            if let (true, Children::Loaded(children)) = (node.expanded, &node.children) {
+                stack.extend(children.iter().rev());
+            }
+        }
+
+        rows
+    }
+
+    /// All there is to tell about `node`, as label and value.
+    pub fn details(&self, node: usize) -> Vec<(&'static str, String)> {
+        let nodes = self.nodes.lock().unwrap();
+        let Some(node) = nodes.get(node) else {
+            return Vec::new();
+        };
+
+        let mut details = vec![
+            ("Bus name", node.object.name.clone()),
+            ("Path", node.object.path.to_string()),
+        ];
+        match &node.info {
+            Info::Loading => details.push(("Status", "loading".to_string())),
+            Info::Failed(e) => details.push(("Error", e.clone())),
+            Info::Loaded(info) => details.extend([
+                ("Role", info.role.clone()),
+                ("Name", info.name.clone()),
+                ("Children", info.child_count.to_string()),
+                ("States", info.states.join(", ")),
+                ("Interfaces", info.interfaces.join(", ")),
+            ]),
+        }
+        if let Children::Failed(e) = &node.children {
+            details.push(("Children error", e.clone()));
+        }
+
+        details
+    }
+}
+
+fn row_text(node: &Node) -> String {
+    let has_children = match &node.info {
+        Info::Loaded(info) => info.child_count > 0,
+        _ => !matches!(&node.children, Children::Loaded(children) if children.is_empty()),
+    };
+    let marker = match (has_children, node.expanded) {
+        (false, _) => "  ",
+        (true, true) => "▾ ",
+        (true, false) => "▸ ",
+    };
+
+    let mut text = format!("{}{marker}", "  ".repeat(node.depth));
+    match &node.info {
+        Info::Loading => text.push('…'),
+        Info::Loaded(info) => {
+            text.push_str(&format!(
+                "{} \"{}\" ({})",
+                info.role, info.name, info.child_count
+            ));
+        }
+        Info::Failed(e) => text.push_str(&format!("{}: {e}", node.object.path)),
+    }
+    match &node.children {
+        Children::Loading if node.expanded => text.push_str(" loading children…"),
+        Children::Failed(_) if node.expanded => text.push_str(" no children, see details"),
+        _ => {}
+    }
+
+    text
+}
+
+/// A proxy for an accessible object that is looked at once.
+pub async fn accessible(
+    conn: &Connection,
+    object: &ObjectRef,
+) -> zbus::Result<AccessibleProxy<'static>> {
+    ProxyBuilder::new(conn)
+        .interface("org.a11y.atspi.Accessible")?
+        .path(object.path.clone())?
+        .destination(object.name.clone())?
+        // There is no need to follow the properties of an object looked at once.
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+/// A call to an application that may not answer.
+pub async fn within<T>(call: impl Future<Output = zbus::Result<T>>) -> Result<T, String> {
+    match timeout(CALL_DEADLINE, call).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("no answer".to_string()),
+    }
+}
+
+// The nodes are held weakly, so a closed explorer is not asked for more.
+// Returns None if the explorer was closed.
+async fn load_info(conn: &Connection, nodes: &Weak<Mutex<Vec<Node>>>, node: usize) -> Option<()> {
+    let object = nodes.upgrade()?.lock().unwrap()[node].object.clone();
+    let info = match within(NodeInfo::fetch(conn, &object)).await {
+        Ok(info) => Info::Loaded(info),
+        Err(e) => Info::Failed(e),
+    };
+    nodes.upgrade()?.lock().unwrap()[node].info = info;
+    Some(())
+}
+
+// Ask `node` for its children, then each child about itself.
+async fn load_children(conn: &Connection, nodes: &Weak<Mutex<Vec<Node>>>, node: usize) {
+    let Some(object) = nodes
+        .upgrade()
+        .map(|nodes| nodes.lock().unwrap()[node].object.clone())
+    else {
+        return;
+    };
+    let children = within(async { accessible(conn, &object).await?.get_children().await }).await;
+
+    let ids: Vec<usize> = {
+        let Some(nodes) = nodes.upgrade() else {
+            return;
+        };
+        let mut nodes = nodes.lock().unwrap();
+        let children = match children {
+            Ok(children) => children,
+            Err(e) => {
+                nodes[node].children = Children::Failed(e);
+                return;
+            }
+        };
+
+        let depth = nodes[node].depth + 1;
+        let first = nodes.len();
+        nodes.extend(
+            children
+                .into_iter()
+                .map(|child| Node::new(child, Some(node), depth)),
+        );
+        let ids: Vec<usize> = (first..nodes.len()).collect();
+        nodes[node].children = Children::Loaded(ids.clone());
+        ids
+    };
+
+    // One at a time, an application with many children should not be flooded.
+    for id in ids {
+        if load_info(conn, nodes, id).await.is_none() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,1787 @@
+use crate::{
+    bus::{Server, Talker},
+    category::Category,
+    control,
+    errors::{ErrorEntry, ErrorSort},
+    history::Resolution,
+    tree::Explorer,
+    App, Window,
+};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{
+        Axis, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, GraphType, List, ListItem,
+        ListState, Paragraph, Row, Sparkline, Table, TableState,
+    },
+    Frame,
+};
+use std::{
+    cell::RefCell,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+// Categories shown as columns in the top talkers table.
+const TALKER_COLUMNS: [Category; 7] = [
+    Category::Object,
+    Category::Window,
+    Category::Document,
+    Category::Focus,
+    Category::Mouse,
+    Category::Keyboard,
+    Category::Terminal,
+];
+
+// The layout of the categorized signals table.
+const CATEGORY_GRID: [[Category; 4]; 3] = [
+    [
+        Category::Keyboard,
+        Category::Mouse,
+        Category::Focus,
+        Category::Window,
+    ],
+    [
+        Category::Object,
+        Category::Document,
+        Category::Terminal,
+        Category::Cache,
+    ],
+    [
+        Category::Available,
+        Category::Listeners,
+        Category::Other,
+        Category::Error,
+    ],
+];
+
+// Style of a category's count.
+fn category_style(category: Category) -> Style {
+    let style = Style::default()
+        .bg(Color::Black)
+        .add_modifier(Modifier::BOLD);
+
+    match category {
+        Category::Available | Category::Listeners => style.fg(Color::LightGreen),
+        Category::Other => style.fg(Color::LightRed),
+        Category::Error => style.fg(Color::White).bg(Color::Red),
+        _ => style.fg(Color::LightBlue),
+    }
+}
+
+/// Column the top talkers table is sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TalkerSort {
+    #[default]
+    Total,
+    Category(Category),
+    Name,
+}
+
+impl TalkerSort {
+    /// Cycle through the columns, left to right.
+    pub fn next(self) -> TalkerSort {
+        match self {
+            TalkerSort::Total => TalkerSort::Category(TALKER_COLUMNS[0]),
+            TalkerSort::Category(category) => {
+                match TALKER_COLUMNS.iter().position(|c| *c == category) {
+                    Some(i) if i + 1 < TALKER_COLUMNS.len() => {
+                        TalkerSort::Category(TALKER_COLUMNS[i + 1])
+                    }
+                    _ => TalkerSort::Name,
+                }
+            }
+            TalkerSort::Name => TalkerSort::Total,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            TalkerSort::Total => "Total",
+            TalkerSort::Category(category) => category.name(),
+            TalkerSort::Name => "Application",
+        }
+    }
+}
+
+/// The panes of the dashboard that can take the focus, in Tab order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pane {
+    #[default]
+    Servers,
+    Talkers,
+    Categories,
+    Errors,
+}
+
+impl Pane {
+    const ALL: [Pane; 4] = [Pane::Servers, Pane::Talkers, Pane::Categories, Pane::Errors];
+
+    pub fn next(self) -> Pane {
+        Pane::ALL[(self as usize + 1) % Pane::ALL.len()]
+    }
+
+    pub fn prev(self) -> Pane {
+        Pane::ALL[(self as usize + Pane::ALL.len() - 1) % Pane::ALL.len()]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Pane::Servers => "servers",
+            Pane::Talkers => "top talkers",
+            Pane::Categories => "categories",
+            Pane::Errors => "errors",
+        }
+    }
+}
+
+// The focused pane stands out by its border.
+fn border_type(focused: bool) -> BorderType {
+    if focused {
+        BorderType::Thick
+    } else {
+        BorderType::Rounded
+    }
+}
+
+/// How the panes of the dashboard are arranged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Preset {
+    /// Pick one of the others by the size of the terminal.
+    #[default]
+    Auto,
+    /// The monitor, the rates and the focused pane, for small terminals.
+    Compact,
+    /// The monitor over a row of the other panes.
+    Wide,
+    /// The panes stacked, for narrow terminals.
+    Tall,
+}
+
+impl Preset {
+    pub fn next(self) -> Preset {
+        match self {
+            Preset::Auto => Preset::Compact,
+            Preset::Compact => Preset::Wide,
+            Preset::Wide => Preset::Tall,
+            Preset::Tall => Preset::Auto,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Preset::Auto => "auto",
+            Preset::Compact => "compact",
+            Preset::Wide => "wide",
+            Preset::Tall => "tall",
+        }
+    }
+
+    /// The preset to draw `area` with. A cell is about twice as high as it is wide,
+    /// so a terminal is narrow when it has fewer than two columns per row.
+    fn fit(self, area: Rect) -> Preset {
+        match self {
+            Preset::Auto if area.width < 80 || area.height < 24 => Preset::Compact,
+            Preset::Auto if area.width < area.height * 2 => Preset::Tall,
+            Preset::Auto => Preset::Wide,
+            preset => preset,
+        }
+    }
+}
+
+/// Where the panels of the dashboard are drawn, panels without an area are not shown.
+#[derive(Debug, Default)]
+struct Areas {
+    monitor: Option<Rect>,
+    rates: Option<Rect>,
+    panes: Vec<(Pane, Rect)>,
+}
+
+impl Areas {
+    fn new(area: Rect, state: &UiState) -> Areas {
+        if state.maximized {
+            return Areas {
+                panes: vec![(state.focus, area)],
+                ..Areas::default()
+            };
+        }
+
+        let split = |area: Rect, direction: Direction, constraints: &[Constraint]| {
+            Layout::default()
+                .direction(direction)
+                .constraints(constraints)
+                .split(area)
+        };
+
+        match state.layout.fit(area) {
+            Preset::Compact => {
+                let rows = split(
+                    area,
+                    Direction::Vertical,
+                    &[
+                        Constraint::Min(3),
+                        Constraint::Length(8),
+                        Constraint::Percentage(50),
+                    ],
+                );
+                Areas {
+                    monitor: Some(rows[0]),
+                    rates: Some(rows[1]),
+                    panes: vec![(state.focus, rows[2])],
+                }
+            }
+            Preset::Tall => {
+                let rows = split(
+                    area,
+                    Direction::Vertical,
+                    &[
+                        Constraint::Percentage(25),
+                        Constraint::Length(10),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(25),
+                        Constraint::Min(0),
+                    ],
+                );
+                let counts = split(
+                    rows[1],
+                    Direction::Horizontal,
+                    &[Constraint::Percentage(50), Constraint::Percentage(50)],
+                );
+                Areas {
+                    monitor: Some(rows[0]),
+                    rates: Some(counts[0]),
+                    panes: vec![
+                        (Pane::Categories, counts[1]),
+                        (Pane::Errors, rows[2]),
+                        (Pane::Servers, rows[3]),
+                        (Pane::Talkers, rows[4]),
+                    ],
+                }
+            }
+            _ => {
+                let chunks = split(
+                    area,
+                    Direction::Vertical,
+                    &[Constraint::Percentage(40), Constraint::Percentage(60)],
+                );
+                let bottom = split(
+                    chunks[1],
+                    Direction::Horizontal,
+                    &[Constraint::Percentage(33), Constraint::Percentage(67)],
+                );
+                let bottom_left = split(
+                    bottom[0],
+                    Direction::Vertical,
+                    &[Constraint::Length(8), Constraint::Min(0)],
+                );
+                let bottom_right = split(
+                    bottom[1],
+                    Direction::Horizontal,
+                    &[Constraint::Percentage(30), Constraint::Percentage(70)],
+                );
+                let servers_area = split(
+                    bottom_right[1],
+                    Direction::Vertical,
+                    &[Constraint::Percentage(50), Constraint::Percentage(50)],
+                );
+                Areas {
+                    monitor: Some(chunks[0]),
+                    rates: Some(bottom_left[0]),
+                    panes: vec![
+                        (Pane::Servers, servers_area[0]),
+                        (Pane::Talkers, servers_area[1]),
+                        (Pane::Categories, bottom_left[1]),
+                        (Pane::Errors, bottom_right[0]),
+                    ],
+                }
+            }
+        }
+    }
+
+    fn pane(&self, pane: Pane) -> Option<Rect> {
+        self.panes
+            .iter()
+            .find(|(p, _)| *p == pane)
+            .map(|(_, area)| *area)
+    }
+}
+
+/// Widths of the columns of `rows` that fit their widest cells, each at least `min`.
+fn fit_widths<'a>(rows: impl IntoIterator<Item = &'a Vec<String>>, min: u16) -> Vec<u16> {
+    let mut widths = Vec::new();
+    for row in rows {
+        widths.resize(widths.len().max(row.len()), min);
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(Span::raw(cell.as_str()).width() as u16);
+        }
+    }
+    widths
+}
+
+// The rows of a list as they were drawn.
+#[derive(Debug, Default)]
+struct ListHits {
+    // Screen rows the items were drawn on, from the first visible item.
+    top: u16,
+    bottom: u16,
+    // Index of the first visible item.
+    offset: usize,
+    heights: Vec<u16>,
+}
+
+/// Where the panes and their rows were last drawn, to tell what a mouse click hits.
+#[derive(Debug, Default)]
+pub struct Hits {
+    panes: Vec<(Pane, Rect)>,
+    lists: Vec<(Pane, ListHits)>,
+    // Widths of the columns of the categorized signals.
+    category_columns: Vec<u16>,
+}
+
+impl Hits {
+    /// The pane at a position on the screen.
+    pub fn pane_at(&self, column: u16, row: u16) -> Option<Pane> {
+        let at = Rect::new(column, row, 1, 1);
+        self.panes
+            .iter()
+            .find(|(_, area)| area.intersects(at))
+            .map(|(pane, _)| *pane)
+    }
+
+    /// The item of the list in `pane` drawn on screen row `row`.
+    pub fn item_at(&self, pane: Pane, row: u16) -> Option<usize> {
+        let (_, list) = self.lists.iter().find(|(p, _)| *p == pane)?;
+        if !(list.top..list.bottom).contains(&row) {
+            return None;
+        }
+
+        let mut y = list.top;
+        for (i, height) in list.heights.iter().enumerate().skip(list.offset) {
+            y += height;
+            if row < y {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// The category whose name or count is at a position on the screen.
+    pub fn category_at(&self, column: u16, row: u16) -> Option<Category> {
+        let (_, area) = self.panes.iter().find(|(p, _)| *p == Pane::Categories)?;
+        // Within the borders, each row of the grid is a line of names, one of counts and a margin.
+        let x = column.checked_sub(area.x + 1)?;
+        let y = row.checked_sub(area.y + 1)?;
+        let line = CATEGORY_GRID.get((y / 3) as usize)?;
+        if y % 3 == 2 {
+            return None;
+        }
+
+        // A column ends with the space after it.
+        let mut right = 0;
+        let column = self.category_columns.iter().position(|width| {
+            right += width + 1;
+            x < right
+        })?;
+        line.get(column).copied()
+    }
+
+    fn list(&mut self, pane: Pane, area: Rect, header: u16, offset: usize, heights: Vec<u16>) {
+        self.lists.push((
+            pane,
+            ListHits {
+                top: area.y + 1 + header,
+                bottom: area.bottom().saturating_sub(1),
+                offset,
+                heights,
+            },
+        ));
+    }
+}
+
+/// State of the user interface that outlives a single frame.
+#[derive(Debug, Default)]
+pub struct UiState {
+    /// The pane the arrow keys and Enter go to.
+    pub focus: Pane,
+    /// The arrangement of the panes.
+    pub layout: Preset,
+    /// Only the focused pane is shown, on the whole screen.
+    pub maximized: bool,
+    pub show_help: bool,
+    /// The screen is not redrawn on ticks, the counting goes on.
+    pub paused: bool,
+    /// The name of the marker being placed, if one is.
+    pub marker_input: Option<String>,
+    pub talker_sort: TalkerSort,
+    pub talker_sort_reversed: bool,
+    /// The row of the top talkers selected.
+    pub talker_cursor: usize,
+    pub selected_category: Category,
+    pub show_members: bool,
+    pub selected_server: usize,
+    pub show_latency: bool,
+    /// The filter being edited, if it is.
+    pub filter_input: Option<String>,
+    pub filter_error: Option<String>,
+    pub show_log: bool,
+    /// The newest event log entry shown, `None` to follow the log.
+    pub log_anchor: Option<u64>,
+    pub log_search: String,
+    /// The search is being typed.
+    pub log_searching: bool,
+    /// The entry the search is at.
+    pub log_match: Option<u64>,
+    /// The accessible tree being explored, if it is.
+    pub explorer: Option<Explorer>,
+    /// The row of the tree explorer selected.
+    pub tree_cursor: usize,
+    pub show_alerts: bool,
+    pub show_history: bool,
+    /// The resolution the history chart is shown at.
+    pub history_zoom: Resolution,
+    pub show_mix: bool,
+    pub error_sort: ErrorSort,
+    pub show_errors: bool,
+    /// The error selected, in the errors pane and the error details.
+    pub error_cursor: usize,
+    pub error_expanded: bool,
+    /// How writing the last session report went.
+    pub report_status: Arc<Mutex<Option<String>>>,
+    /// Where things were drawn, for the mouse.
+    pub hits: RefCell<Hits>,
+}
+
+impl UiState {
+    /// Whether a pop-up covers the dashboard.
+    pub fn shows_popup(&self) -> bool {
+        self.show_members
+            || self.show_latency
+            || self.show_log
+            || self.show_alerts
+            || self.show_history
+            || self.show_mix
+            || self.show_errors
+            || self.show_help
+            || self.explorer.is_some()
+            || self.filter_input.is_some()
+            || self.marker_input.is_some()
+    }
+}
+
+/// The drill-down of a category: its signal members with their counts and rates.
+fn render_members(f: &mut Frame, area: Rect, app: &App, category: Category) {
+    let mut members = app.members.of(category);
+    members.sort_by_key(|(_, tally)| std::cmp::Reverse(tally.total));
+    let category_total = app.tally.counter(category).load().max(1);
+
+    let header = vec![
+        "Member".to_string(),
+        "Count".to_string(),
+        "Rate/s".to_string(),
+        "Share".to_string(),
+    ];
+    let rows: Vec<Vec<String>> = members
+        .into_iter()
+        .map(|(member, tally)| {
+            let share = tally.total as f64 * 100.0 / category_total as f64;
+            vec![
+                member,
+                tally.total.to_string(),
+                tally.rate.to_string(),
+                format!("{share:.1}%"),
+            ]
+        })
+        .collect();
+
+    let mut widths: Vec<Constraint> = fit_widths(std::iter::once(&header).chain(&rows), 7)
+        .into_iter()
+        .map(Constraint::Length)
+        .collect();
+    // The member names take what the numbers leave.
+    widths[0] = Constraint::Min(24);
+
+    let table = Table::new(rows.into_iter().map(Row::new))
+        .header(
+            Row::new(header)
+                .style(Style::default().fg(Color::LightYellow))
+                .bottom_margin(1),
+        )
+        .style(Style::default().fg(Color::LightBlue))
+        .widths(&widths)
+        .column_spacing(1)
+        .block(
+            Block::default()
+                .title(format!("{} signals (Esc to close)", category.name()))
+                .border_style(Style::default().fg(Color::LightYellow))
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .borders(Borders::ALL),
+        );
+    f.render_widget(table, area);
+}
+
+/// The bus names of the top talkers, in the order they are shown.
+pub fn talker_bus_names(app: &App, state: &UiState) -> Vec<String> {
+    let known = app.servers.talkers();
+    let mut talkers: Vec<&Talker> = known.iter().map(Arc::as_ref).collect();
+    talkers.push(&app.servers.unresolved);
+    sort_talkers(&mut talkers, state);
+
+    talkers
+        .iter()
+        .map(|talker| talker.bus_name.clone())
+        .collect()
+}
+
+fn sort_talkers(talkers: &mut [&Talker], state: &UiState) {
+    match state.talker_sort {
+        TalkerSort::Total => talkers.sort_by_key(|t| std::cmp::Reverse(t.counts.total())),
+        TalkerSort::Category(category) => {
+            talkers.sort_by_key(|t| std::cmp::Reverse(t.counts.get(category).load()))
+        }
+        TalkerSort::Name => talkers.sort_by(|a, b| a.accessible_name.cmp(&b.accessible_name)),
+    }
+    if state.talker_sort_reversed {
+        talkers.reverse();
+    }
+}
+
+/// The top talkers table: events per application, sorted as the user asked.
+fn render_talkers(
+    f: &mut Frame,
+    area: Rect,
+    app: &App,
+    state: &UiState,
+    table_state: &mut TableState,
+) {
+    let known = app.servers.talkers();
+    let mut talkers: Vec<&Talker> = known.iter().map(Arc::as_ref).collect();
+    talkers.push(&app.servers.unresolved);
+    sort_talkers(&mut talkers, state);
+
+    let header_style = Style::default().fg(Color::LightYellow);
+    let sorted_style = Style::default()
+        .fg(Color::LightMagenta)
+        .add_modifier(ratatui::style::Modifier::BOLD);
+    let column_style = |sort: TalkerSort| {
+        if sort == state.talker_sort {
+            sorted_style
+        } else {
+            header_style
+        }
+    };
+
+    let mut header = vec![
+        Cell::from("Application").style(column_style(TalkerSort::Name)),
+        Cell::from("Bus").style(header_style),
+        Cell::from("Total").style(column_style(TalkerSort::Total)),
+    ];
+    header.extend(TALKER_COLUMNS.iter().map(|category| {
+        Cell::from(category.name()).style(column_style(TalkerSort::Category(*category)))
+    }));
+
+    let rows: Vec<Vec<String>> = talkers
+        .into_iter()
+        .map(|talker| {
+            let name = if talker.has_departed() {
+                format!("{} (departed)", talker.accessible_name)
+            } else {
+                talker.accessible_name.clone()
+            };
+            let mut cells = vec![
+                name,
+                talker.bus_name.clone(),
+                talker.counts.total().to_string(),
+            ];
+            cells.extend(
+                TALKER_COLUMNS
+                    .iter()
+                    .map(|category| talker.counts.get(*category).load().to_string()),
+            );
+            cells
+        })
+        .collect();
+
+    // The numbers are as wide as the widest count or name of their category,
+    // the application names take what is left.
+    let names = [
+        "Application".to_string(),
+        "Bus".to_string(),
+        "Total".to_string(),
+    ]
+    .into_iter()
+    .chain(TALKER_COLUMNS.iter().map(|c| c.name().to_string()))
+    .collect();
+    let mut widths: Vec<Constraint> = fit_widths(std::iter::once(&names).chain(&rows), 5)
+        .into_iter()
+        .map(Constraint::Length)
+        .collect();
+    widths[0] = Constraint::Min(16);
+
+    let order = if state.talker_sort_reversed {
+        "ascending"
+    } else {
+        "descending"
+    };
+
+    let table = Table::new(rows.into_iter().map(Row::new))
+        .header(Row::new(header).bottom_margin(1))
+        .style(Style::default().fg(Color::LightBlue))
+        .widths(&widths)
+        .column_spacing(1)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .title(format!(
+                    "Top talkers, by {} {order} ('s' sort, 'r' reverse, Enter tree)",
+                    state.talker_sort.label()
+                ))
+                .border_style(Style::default().fg(Color::LightBlue))
+                .border_type(border_type(state.focus == Pane::Talkers))
+                .borders(Borders::ALL),
+        );
+    f.render_stateful_widget(table, area, table_state);
+}
+
+pub fn ui(f: &mut Frame, app: Arc<App>, state: &UiState) {
+    let screen = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(f.size());
+
+    let areas = Areas::new(screen[0], state);
+
+    let tick_data = app.tick_data.lock().unwrap();
+
+    // An alert takes over the title of the monitor.
+    let alerts = app.alerts.active();
+    let (monitor_title, monitor_color) = match alerts.first() {
+        Some(alert) => (
+            format!(
+                "ALERT {}: {} ({} active, 'a' history)",
+                alert.rule.name(),
+                alert.message,
+                alerts.len()
+            ),
+            Color::LightRed,
+        ),
+        None => (monitor_title(&app), Color::LightBlue),
+    };
+
+    let sparkline = Sparkline::default()
+        .block(
+            Block::default()
+                .title(monitor_title)
+                .border_style(Style::default().fg(monitor_color))
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .borders(Borders::ALL),
+        )
+        .data(tick_data.as_slice())
+        .style(Style::default().fg(Color::Yellow));
+
+    // Rates: current, max, mean, total
+    let values = vec![
+        app.rt_stats.rate.load().to_string(),
+        app.rt_stats.max.load().to_string(),
+        app.rt_stats.mean.load().to_string(),
+        app.tally.total.load().to_string(),
+    ];
+    let value_style = Style::default()
+        .fg(Color::LightBlue)
+        .bg(Color::Black)
+        .add_modifier(ratatui::style::Modifier::BOLD);
+    let total_style = Style::default()
+        .fg(Color::LightMagenta)
+        .bg(Color::Black)
+        .add_modifier(ratatui::style::Modifier::BOLD | ratatui::style::Modifier::UNDERLINED);
+    let column_data = values.iter().enumerate().map(|(i, value)| {
+        Cell::from(value.clone()).style(if i == 3 { total_style } else { value_style })
+    });
+
+    // Rolling windows: mean and peak over the last 1, 5 and 15 minutes
+    let windows = *app.rt_stats.windows.lock().unwrap();
+    let window_style = Style::default().fg(Color::LightBlue).bg(Color::Black);
+    let window_line = |label: &str, value: fn(&Window) -> String| {
+        std::iter::once(label.to_string())
+            .chain(windows.iter().map(value))
+            .collect::<Vec<_>>()
+    };
+    let labels = window_line("", Window::label);
+    let means = window_line("Mean", |w| format!("{:.1}", w.mean));
+    let peaks = window_line("Peak", |w| w.peak.to_string());
+    let window_row = |line: &Vec<String>| {
+        Row::new(line.iter().enumerate().map(|(i, cell)| {
+            Cell::from(cell.clone()).style(if i == 0 {
+                Style::default()
+            } else {
+                window_style
+            })
+        }))
+    };
+
+    let heading = ["Last", "Peak", "Mean", "Total"].map(String::from).to_vec();
+    let rate_widths: Vec<Constraint> = fit_widths([&heading, &values, &labels, &means, &peaks], 10)
+        .into_iter()
+        .map(Constraint::Length)
+        .collect();
+
+    let rates = Table::new([
+        Row::new(heading.clone()).style(Style::default().fg(Color::LightYellow)),
+        Row::new(column_data).bottom_margin(1),
+        Row::new(labels.clone()).style(Style::default().fg(Color::LightYellow)),
+        window_row(&means),
+        window_row(&peaks),
+    ])
+    .style(Style::default().fg(Color::LightYellow))
+    .widths(&rate_widths)
+    .column_spacing(1)
+    .block(
+        Block::default()
+            .title("AT-SPI2 signal rate dashboard:")
+            .border_style(Style::default().fg(Color::LightYellow))
+            .border_type(ratatui::widgets::BorderType::Rounded)
+            .borders(Borders::ALL),
+    );
+
+    // The categories are laid out in a grid, each column as wide as its widest name or count.
+    let counts: Vec<Vec<String>> = CATEGORY_GRID
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|category| app.tally.counter(*category).load().to_string())
+                .collect()
+        })
+        .collect();
+    let names: Vec<Vec<String>> = CATEGORY_GRID
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|category| category.name().to_string())
+                .collect()
+        })
+        .collect();
+    let category_columns = fit_widths(names.iter().chain(&counts), 8);
+    let category_widths: Vec<Constraint> = category_columns
+        .iter()
+        .copied()
+        .map(Constraint::Length)
+        .collect();
+
+    let categories = Table::new(CATEGORY_GRID.iter().enumerate().flat_map(|(i, row)| {
+        let names = Row::new(row.iter().map(|category| {
+            let style = Style::default().fg(Color::LightYellow);
+            if state.show_members && *category == state.selected_category {
+                Cell::from(category.name()).style(style.add_modifier(Modifier::REVERSED))
+            } else if *category == state.selected_category {
+                Cell::from(category.name()).style(style.add_modifier(Modifier::UNDERLINED))
+            } else {
+                Cell::from(category.name()).style(style)
+            }
+        }));
+        let counts =
+            Row::new(row.iter().zip(&counts[i]).map(|(category, count)| {
+                Cell::from(count.clone()).style(category_style(*category))
+            }));
+        let counts = if i + 1 < CATEGORY_GRID.len() {
+            counts.bottom_margin(1)
+        } else {
+            counts
+        };
+        [names, counts]
+    }))
+    .style(Style::default().fg(Color::LightYellow))
+    .widths(&category_widths)
+    .column_spacing(1)
+    .block(
+        Block::default()
+            .title("Categorized signals ('c' select, Enter members)")
+            .border_style(Style::default().fg(Color::LightYellow))
+            .border_type(border_type(state.focus == Pane::Categories))
+            .borders(Borders::ALL),
+    );
+
+    let errors = app.errors.sorted(state.error_sort);
+    let error_list = List::new(
+        errors
+            .iter()
+            .map(|entry| ListItem::new(error_line(entry)))
+            .collect::<Vec<ListItem<'_>>>(),
+    )
+    .block(
+        Block::default()
+            .title(format!(
+                "Errors, by {} ('x' details)",
+                state.error_sort.label()
+            ))
+            .border_style(Style::default().fg(Color::LightRed))
+            .border_type(border_type(state.focus == Pane::Errors))
+            .borders(Borders::ALL),
+    )
+    .style(Style::default().fg(Color::LightRed))
+    .highlight_style(Style::default().fg(Color::Red))
+    .highlight_symbol(">> ");
+
+    let server_stats: Vec<_> = app
+        .servers
+        .live()
+        .into_iter()
+        .chain(app.servers.gone())
+        .collect();
+
+    let server_items: Vec<ListItem> = server_stats
+        .iter()
+        .map(|server| {
+            if let Ok(guard) = server.try_lock() {
+                let tree = match &guard.tree {
+                    Some(tree) => tree.to_string(),
+                    None => "tree: not crawled yet".to_string(),
+                };
+                match guard.departed {
+                    Some(departed) => ListItem::new(format!(
+                        "{} (departed {}s ago):\n\t{}\n\t{}\n\t{}\n\t{tree}\n",
+                        guard.accessible_name,
+                        departed.elapsed().as_secs(),
+                        guard.stats,
+                        guard.stats.percentiles(),
+                        guard.stats.health()
+                    ))
+                    .style(Style::default().fg(Color::DarkGray)),
+                    None => ListItem::new(format!(
+                        "{}:\n\t{}\n\t{}\n\t{}\n\t{tree}\n",
+                        guard.accessible_name,
+                        guard.stats,
+                        guard.stats.percentiles(),
+                        guard.stats.health()
+                    )),
+                }
+            } else {
+                ListItem::new("Server contended for lock")
+            }
+        })
+        .collect();
+    let server_heights: Vec<u16> = server_items
+        .iter()
+        .map(|item| item.height() as u16)
+        .collect();
+
+    let server_list = List::new(server_items)
+        .block(
+            Block::default()
+                .title("Server response time stats (Enter latency, 't' tree)")
+                .border_style(Style::default().fg(Color::LightBlue))
+                .border_type(border_type(state.focus == Pane::Servers))
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().fg(Color::LightYellow))
+        .highlight_style(Style::default().fg(Color::Blue))
+        .highlight_symbol(">> ");
+
+    let selected_server =
+        (!server_stats.is_empty()).then(|| state.selected_server.min(server_stats.len() - 1));
+    let mut server_list_state = ListState::default().with_selected(selected_server);
+
+    // The errors and top talkers show their selection while they have the focus.
+    let mut error_list_state = ListState::default().with_selected(
+        (state.focus == Pane::Errors && !errors.is_empty())
+            .then(|| state.error_cursor.min(errors.len() - 1)),
+    );
+    let talkers = app.servers.talkers().len() + 1;
+    let mut talkers_state = TableState::default().with_selected(
+        (state.focus == Pane::Talkers).then(|| state.talker_cursor.min(talkers - 1)),
+    );
+
+    if let Some(area) = areas.monitor {
+        f.render_widget(sparkline, area);
+    }
+    if let Some(area) = areas.rates {
+        f.render_widget(rates, area);
+    }
+
+    let mut hits = Hits {
+        panes: areas.panes.clone(),
+        lists: Vec::new(),
+        category_columns,
+    };
+    if let Some(area) = areas.pane(Pane::Categories) {
+        f.render_widget(categories, area);
+    }
+    if let Some(area) = areas.pane(Pane::Errors) {
+        f.render_stateful_widget(error_list, area, &mut error_list_state);
+        hits.list(
+            Pane::Errors,
+            area,
+            0,
+            error_list_state.offset(),
+            vec![1; errors.len()],
+        );
+    }
+    if let Some(area) = areas.pane(Pane::Servers) {
+        f.render_stateful_widget(server_list, area, &mut server_list_state);
+        hits.list(
+            Pane::Servers,
+            area,
+            0,
+            server_list_state.offset(),
+            server_heights,
+        );
+    }
+    if let Some(area) = areas.pane(Pane::Talkers) {
+        render_talkers(f, area, &app, state, &mut talkers_state);
+        // The talkers have a header and a margin over the rows.
+        hits.list(
+            Pane::Talkers,
+            area,
+            2,
+            talkers_state.offset(),
+            vec![1; talkers],
+        );
+    }
+    *state.hits.borrow_mut() = hits;
+    f.render_widget(
+        status_bar(&app, state, state.layout.fit(screen[0])),
+        screen[1],
+    );
+
+    if state.show_members {
+        let area = centered_rect(60, 60, f.size());
+        f.render_widget(Clear, area);
+        render_members(f, area, &app, state.selected_category);
+    }
+
+    if state.show_log {
+        let area = centered_rect(90, 80, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(event_log(&app, state, area.height.saturating_sub(2)), area);
+    }
+
+    if state.show_alerts {
+        let area = centered_rect(80, 60, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(alert_history(&app), area);
+    }
+
+    if state.show_errors {
+        let area = centered_rect(90, 80, f.size());
+        f.render_widget(Clear, area);
+        render_errors(f, area, &app, state);
+    }
+
+    if state.show_history {
+        let area = centered_rect(90, 70, f.size());
+        f.render_widget(Clear, area);
+        render_history(f, area, &app, state.history_zoom);
+    }
+
+    if state.show_mix {
+        let area = centered_rect(90, 70, f.size());
+        f.render_widget(Clear, area);
+        render_mix(f, area, &app);
+    }
+
+    if let Some(explorer) = state.explorer.as_ref() {
+        let area = centered_rect(90, 80, f.size());
+        f.render_widget(Clear, area);
+        render_tree(f, area, explorer, state.tree_cursor);
+    }
+
+    if let Some(input) = state.filter_input.as_deref() {
+        let area = centered_rect(60, 20, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(filter_editor(input, state.filter_error.as_deref()), area);
+    }
+
+    if let Some(server) = selected_server.filter(|_| state.show_latency) {
+        let area = centered_rect(80, 60, f.size());
+        f.render_widget(Clear, area);
+        match server_stats[server].try_lock() {
+            Ok(guard) => render_latency(f, area, &guard),
+            Err(_) => f.render_widget(Paragraph::new("Server contended for lock"), area),
+        }
+    }
+
+    if let Some(input) = state.marker_input.as_deref() {
+        let area = centered_rect(50, 15, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(marker_editor(input), area);
+    }
+
+    if state.show_help {
+        let area = centered_rect(70, 90, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(help(), area);
+    }
+}
+
+/// The line a marker is named on.
+fn marker_editor(input: &str) -> Paragraph<'_> {
+    Paragraph::new(vec![
+        Line::from(vec![Span::raw(input), Span::raw("_")]),
+        Line::styled(
+            "e.g. \"dialog opened\", empty for a numbered marker",
+            Style::default().fg(Color::Gray),
+        ),
+    ])
+    .block(
+        Block::default()
+            .title("Marker (Enter place, Esc cancel)")
+            .border_style(Style::default().fg(Color::LightMagenta))
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL),
+    )
+}
+
+/// The line at the bottom: the focused pane, the main keys, the layout, the last marker
+/// and how the last report went.
+fn status_bar<'a>(app: &App, state: &UiState, preset: Preset) -> Paragraph<'a> {
+    let key = Style::default().fg(Color::Black).bg(Color::LightBlue);
+    let focus = if state.maximized {
+        format!(" {} (maximized) ", state.focus.name())
+    } else {
+        format!(" {} ", state.focus.name())
+    };
+    let layout = if state.layout == Preset::Auto {
+        format!("auto, {}", preset.name())
+    } else {
+        preset.name().to_string()
+    };
+    let mut spans = vec![
+        Span::styled(focus, key.add_modifier(Modifier::BOLD)),
+        Span::raw(format!(" {layout} │")),
+        Span::raw(" Tab focus  ↑/↓ select  Enter details  ? help  q quit"),
+    ];
+    if state.paused {
+        spans.insert(
+            0,
+            Span::styled(
+                " PAUSED ",
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::LightYellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        );
+    }
+    if let Some(marker) = app.markers.lock().unwrap().last() {
+        spans.push(Span::raw("  │ "));
+        spans.push(Span::styled(
+            format!("{} at {}", marker.name, clock(marker.second)),
+            Style::default().fg(Color::LightMagenta),
+        ));
+    }
+    if let Some(status) = state.report_status.lock().unwrap().as_deref() {
+        spans.push(Span::raw("  │ "));
+        spans.push(Span::styled(
+            status.to_string(),
+            Style::default().fg(Color::LightYellow),
+        ));
+    }
+
+    Paragraph::new(Line::from(spans)).style(Style::default().fg(Color::Gray))
+}
+
+// Every key of the dashboard, by where it applies.
+const HELP: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Everywhere",
+        &[
+            ("Tab / Shift-Tab", "focus the next / previous pane"),
+            ("↑/↓ or k/j", "select in the focused pane"),
+            ("←/→", "select in the categories or members"),
+            ("Enter", "details of the selection"),
+            ("click", "focus the pane and select what was clicked"),
+            ("wheel", "scroll the pane under the pointer, or the pop-up"),
+            ("z", "maximize the focused pane"),
+            ("v", "next layout: auto, compact, wide, tall"),
+            ("Esc", "close the pop-ups, or their own key"),
+            ("?", "this help"),
+            ("space", "pause the screen, the counting goes on"),
+            ("q", "quit"),
+        ],
+    ),
+    (
+        "Dashboard",
+        &[
+            ("s / r", "sort / reverse the top talkers"),
+            ("c", "select the next category"),
+            ("l", "latency of the selected server"),
+            ("t", "accessible tree of the selected server"),
+            ("e", "event log"),
+            ("f", "edit the filter"),
+            ("a", "alert history"),
+            ("h", "history chart, '+'/'-' zoom"),
+            ("m", "traffic mix"),
+            ("x", "error details"),
+            ("w", "write a session report"),
+            ("R", "reset all counters"),
+            ("C", "reset the selected category"),
+            ("M", "place a named marker"),
+        ],
+    ),
+    (
+        "Event log",
+        &[
+            ("p or space", "pause / follow"),
+            ("↑/↓ PgUp/PgDn Home/End", "scroll"),
+            ("/", "search, n / N older / newer match"),
+        ],
+    ),
+    (
+        "Error details",
+        &[("Enter", "expand the selected error"), ("o", "sort order")],
+    ),
+    (
+        "Accessible tree",
+        &[("→ or Enter / ←", "expand / collapse")],
+    ),
+];
+
+/// The help overlay, listing all key bindings.
+fn help<'a>() -> Paragraph<'a> {
+    let mut lines = Vec::new();
+    for (section, keys) in HELP {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::styled(
+            *section,
+            Style::default()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+        for (key, action) in keys.iter() {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {key:<24}"),
+                    Style::default().fg(Color::LightBlue),
+                ),
+                Span::raw(*action),
+            ]));
+        }
+    }
+
+    Paragraph::new(lines).block(
+        Block::default()
+            .title("Keys (Esc or ? to close)")
+            .border_style(Style::default().fg(Color::LightYellow))
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL),
+    )
+}
+
+/// The latest `rows` events, or those up to where the log was paused or searched to.
+fn event_log<'a>(app: &App, state: &UiState, rows: u16) -> List<'a> {
+    let entries = app.event_log.window(state.log_anchor, rows as usize);
+    let search = state.log_search.to_lowercase();
+
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| {
+            let line = entry.line();
+            // The colors of the categorized signals, without the emphasis.
+            let color = match entry.category {
+                Category::Error => Color::LightRed,
+                category => category_style(category).fg.unwrap_or(Color::LightBlue),
+            };
+            let style = Style::default().fg(color);
+            let style = if Some(entry.seq) == state.log_match {
+                style.add_modifier(Modifier::REVERSED)
+            } else if !search.is_empty() && line.to_lowercase().contains(&search) {
+                style.fg(Color::LightYellow)
+            } else {
+                style
+            };
+            ListItem::new(line).style(style)
+        })
+        .collect();
+
+    let position = match state.log_anchor {
+        Some(_) => "paused",
+        None => "following",
+    };
+    let search = if state.log_searching {
+        format!(", search: {}_", state.log_search)
+    } else if state.log_search.is_empty() {
+        String::new()
+    } else if state.log_match.is_none() {
+        format!(", search: {} (no match)", state.log_search)
+    } else {
+        format!(", search: {} ('n' older, 'N' newer)", state.log_search)
+    };
+
+    List::new(items).block(
+        Block::default()
+            .title(format!(
+                "Event log, {position}{search} ('p' pause, ↑/↓/PgUp/PgDn/Home/End scroll, '/' search, Esc close)"
+            ))
+            .border_style(Style::default().fg(Color::LightBlue))
+            .border_type(ratatui::widgets::BorderType::Rounded)
+            .borders(Borders::ALL),
+    )
+}
+
+// An error group on a line: how often, what kind and the message.
+fn error_line(entry: &ErrorEntry) -> String {
+    format!("{:>6}× {}: {}", entry.count, entry.kind, entry.message)
+}
+
+/// The errors met, with the details of the selected one if it is expanded.
+fn render_errors(f: &mut Frame, area: Rect, app: &App, state: &UiState) {
+    let entries = app.errors.sorted(state.error_sort);
+    let cursor = state.error_cursor.min(entries.len().saturating_sub(1));
+    let selected = entries.get(cursor).filter(|_| state.error_expanded);
+
+    let panes = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(match selected {
+            Some(_) => [Constraint::Percentage(60), Constraint::Percentage(40)],
+            None => [Constraint::Percentage(100), Constraint::Length(0)],
+        })
+        .split(area);
+
+    let evicted = match app.errors.evicted() {
+        0 => String::new(),
+        n => format!(", {n} older pushed out"),
+    };
+    let list = List::new(
+        entries
+            .iter()
+            .map(|entry| ListItem::new(error_line(entry)))
+            .collect::<Vec<ListItem<'_>>>(),
+    )
+    .block(
+        Block::default()
+            .title(format!(
+                "Errors ({}), by {}{evicted} (↑/↓ move, Enter expand, 'o' sort, Esc close)",
+                entries.len(),
+                state.error_sort.label()
+            ))
+            .border_style(Style::default().fg(Color::LightRed))
+            .border_type(ratatui::widgets::BorderType::Rounded)
+            .borders(Borders::ALL),
+    )
+    .style(Style::default().fg(Color::LightRed))
+    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut list_state =
+        ListState::default().with_selected((!entries.is_empty()).then_some(cursor));
+    f.render_stateful_widget(list, panes[0], &mut list_state);
+
+    let Some(entry) = selected else {
+        return;
+    };
+    let header = entry.header.clone().unwrap_or_default();
+    let none = || "-".to_string();
+    let details: Vec<Line> = [
+        ("Kind", entry.kind.to_string()),
+        ("Message", entry.message.clone()),
+        ("Count", entry.count.to_string()),
+        (
+            "First seen",
+            format!("{:.3}s", entry.first_seen.as_secs_f64()),
+        ),
+        (
+            "Last seen",
+            format!("{:.3}s", entry.last_seen.as_secs_f64()),
+        ),
+        ("Sender", header.sender.unwrap_or_else(none)),
+        ("Interface", header.interface.unwrap_or_else(none)),
+        ("Member", header.member.unwrap_or_else(none)),
+        ("Path", header.path.unwrap_or_else(none)),
+        ("Detail", header.detail.unwrap_or_else(none)),
+    ]
+    .into_iter()
+    .map(|(label, value)| {
+        Line::from(vec![
+            Span::styled(
+                format!("{label}: "),
+                Style::default().fg(Color::LightYellow),
+            ),
+            Span::raw(value),
+        ])
+    })
+    .collect();
+
+    let details = Paragraph::new(details)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .block(
+            Block::default()
+                .title("Last occurrence")
+                .border_style(Style::default().fg(Color::LightYellow))
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .borders(Borders::ALL),
+        );
+    f.render_widget(details, panes[1]);
+}
+
+/// The alerts raised, those still active first, then the ended ones, latest first.
+fn alert_history<'a>(app: &App) -> List<'a> {
+    let active = app.alerts.active().into_iter().map(|alert| {
+        ListItem::new(format!(
+            "[{:>6}s] {}: {} (ongoing, peak {}/s)",
+            alert.started,
+            alert.rule.name(),
+            alert.message,
+            alert.peak
+        ))
+        .style(Style::default().fg(Color::LightRed))
+    });
+    let ended = app.alerts.history().into_iter().map(|alert| {
+        ListItem::new(format!(
+            "[{:>6}s] {}: {} (lasted {}s, peak {}/s)",
+            alert.started,
+            alert.rule.name(),
+            alert.message,
+            alert.ended.unwrap_or(alert.started) - alert.started,
+            alert.peak
+        ))
+    });
+
+    List::new(active.chain(ended).collect::<Vec<_>>())
+        .block(
+            Block::default()
+                .title("Alerts (Esc to close)")
+                .border_style(Style::default().fg(Color::LightRed))
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().fg(Color::LightYellow))
+}
+
+/// The accessible tree of an application, with the details of the selected node beside it.
+fn render_tree(f: &mut Frame, area: Rect, explorer: &Explorer, cursor: usize) {
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(area);
+
+    let rows = explorer.rows();
+    let cursor = cursor.min(rows.len().saturating_sub(1));
+    let tree = List::new(
+        rows.iter()
+            .map(|row| ListItem::new(row.text.as_str()))
+            .collect::<Vec<ListItem<'_>>>(),
+    )
+    .block(
+        Block::default()
+            .title(format!(
+                "{} accessible tree (↑/↓ move, →/Enter expand, ← collapse, Esc close)",
+                explorer.app_name
+            ))
+            .border_style(Style::default().fg(Color::LightBlue))
+            .border_type(ratatui::widgets::BorderType::Rounded)
+            .borders(Borders::ALL),
+    )
+    .style(Style::default().fg(Color::LightBlue))
+    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let details: Vec<Line> = rows
+        .get(cursor)
+        .map(|row| explorer.details(row.node))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{label}: "),
+                    Style::default().fg(Color::LightYellow),
+                ),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+    let details = Paragraph::new(details)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .block(
+            Block::default()
+                .title("Node")
+                .border_style(Style::default().fg(Color::LightYellow))
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .borders(Borders::ALL),
+        );
+
+    let mut tree_state = ListState::default().with_selected(Some(cursor));
+    f.render_stateful_widget(tree, panes[0], &mut tree_state);
+    f.render_widget(details, panes[1]);
+}
+
+// The filter in effect and how many events it kept out.
+fn monitor_title(app: &App) -> String {
+    let filter = app.filter.borrow();
+    if filter.is_empty() {
+        return "AT-SPI2 signal monitor ('f' filter, 'h' history, 'm' mix)".to_string();
+    }
+
+    format!(
+        "AT-SPI2 signal monitor, filter: {}, {} kept out ('f' edit, 'h' history, 'm' mix)",
+        *filter,
+        app.tally.filtered.load()
+    )
+}
+
+/// The line the filter is edited on, with the reason it was refused, if it was.
+fn filter_editor<'a>(input: &'a str, error: Option<&'a str>) -> Paragraph<'a> {
+    let mut lines = vec![
+        Line::from(vec![Span::raw(input), Span::raw("_")]),
+        Line::from(""),
+        Line::styled(
+            "field=value or field!=value, comma separated",
+            Style::default().fg(Color::Gray),
+        ),
+        Line::styled(
+            "fields: category, member, sender, app, path, detail",
+            Style::default().fg(Color::Gray),
+        ),
+    ];
+    if let Some(error) = error {
+        lines.push(Line::styled(error, Style::default().fg(Color::LightRed)));
+    }
+
+    Paragraph::new(lines).block(
+        Block::default()
+            .title("Filter (Enter apply, Esc cancel)")
+            .border_style(Style::default().fg(Color::LightYellow))
+            .border_type(ratatui::widgets::BorderType::Rounded)
+            .borders(Borders::ALL),
+    )
+}
+
+// Samples in the window the min/max bands of the latency chart are taken over.
+const BAND_WINDOW: usize = 10;
+
+/// The latency of a server over time, between the minimum and maximum of the recent pings.
+fn render_latency(f: &mut Frame, area: Rect, server: &Server) {
+    let millis = |d: Duration| d.as_secs_f64() * 1_000.0;
+    let history: Vec<Option<f64>> = server.rtt_history.iter().map(|r| r.map(millis)).collect();
+
+    let rtt: Vec<(f64, f64)> = history
+        .iter()
+        .enumerate()
+        .filter_map(|(x, y)| y.map(|y| (x as f64, y)))
+        .collect();
+
+    // The band around each sample: min and max of the window that ends there.
+    let (min_band, max_band): (Vec<_>, Vec<_>) = (0..history.len())
+        .filter_map(|x| {
+            let window = history[x.saturating_sub(BAND_WINDOW - 1)..=x]
+                .iter()
+                .flatten();
+            let (min, max) = window.fold((f64::MAX, f64::MIN), |(min, max), y| {
+                (min.min(*y), max.max(*y))
+            });
+            (min <= max).then_some(((x as f64, min), (x as f64, max)))
+        })
+        .unzip();
+
+    let top = rtt.iter().map(|(_, y)| *y).fold(0.0, f64::max).max(1.0) * 1.1;
+    let failed: Vec<(f64, f64)> = history
+        .iter()
+        .enumerate()
+        .filter(|(_, y)| y.is_none())
+        .map(|(x, _)| (x as f64, top))
+        .collect();
+
+    let datasets = vec![
+        Dataset::default()
+            .name("max")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::DarkGray))
+            .data(&max_band),
+        Dataset::default()
+            .name("min")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::DarkGray))
+            .data(&min_band),
+        Dataset::default()
+            .name("rtt")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Yellow))
+            .data(&rtt),
+        Dataset::default()
+            .name("failed")
+            .marker(Marker::Dot)
+            .graph_type(GraphType::Scatter)
+            .style(Style::default().fg(Color::LightRed))
+            .data(&failed),
+    ];
+
+    let samples = history.len().max(1) as f64;
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(format!(
+                    "{} response times, last {} pings (Esc to close)",
+                    server.accessible_name,
+                    history.len()
+                ))
+                .border_style(Style::default().fg(Color::LightBlue))
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .borders(Borders::ALL),
+        )
+        .x_axis(
+            Axis::default()
+                .title("ping")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, samples - 1.0]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("ms")
+                .style(Style::default().fg(Color::Gray))
+                .labels(vec![
+                    Span::raw("0"),
+                    Span::raw(format!("{:.1}", top / 2.0)),
+                    Span::raw(format!("{top:.1}")),
+                ])
+                .bounds([0.0, top]),
+        );
+
+    f.render_widget(chart, area);
+}
+
+// A second of the session as h:mm:ss.
+fn clock(second: usize) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        second / 3600,
+        second / 60 % 60,
+        second % 60
+    )
+}
+
+/// Events per second over the session, at `resolution`.
+/// The minutes and hours show their mean and their busiest second.
+fn render_history(f: &mut Frame, area: Rect, app: &App, resolution: Resolution) {
+    let samples = app.history.lock().unwrap().samples(resolution);
+    let step = resolution.secs() as f64;
+
+    let x = |second: usize| second as f64 / step;
+    let mean: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|(second, sample)| (x(*second), sample.events as f64 / step))
+        .collect();
+    let peak: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|(second, sample)| (x(*second), sample.peak as f64))
+        .collect();
+
+    let mut datasets = Vec::new();
+    if resolution != Resolution::Second {
+        datasets.push(
+            Dataset::default()
+                .name("peak")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::DarkGray))
+                .data(&peak),
+        );
+    }
+    datasets.push(
+        Dataset::default()
+            .name("mean")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Yellow))
+            .data(&mean),
+    );
+
+    let (first, last) = match (samples.first(), samples.last()) {
+        (Some((first, _)), Some((last, _))) => (*first, *last),
+        _ => (0, 0),
+    };
+    let top = peak.iter().map(|(_, y)| *y).fold(0.0, f64::max).max(1.0) * 1.1;
+
+    // The markers in view, as dotted vertical lines.
+    let markers: Vec<control::Marker> = app
+        .markers
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|marker| (first..=last + resolution.secs()).contains(&marker.second))
+        .cloned()
+        .collect();
+    let marker_lines: Vec<(f64, f64)> = markers
+        .iter()
+        .flat_map(|marker| (0..=20).map(move |i| (x(marker.second), top * i as f64 / 20.0)))
+        .collect();
+    if !markers.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .name("markers")
+                .marker(Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(Color::LightMagenta))
+                .data(&marker_lines),
+        );
+    }
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(format!(
+                    "Events per second, by the {}, {} samples ('+'/'-' zoom, Esc to close)",
+                    resolution.name(),
+                    samples.len()
+                ))
+                .border_style(Style::default().fg(Color::LightBlue))
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .borders(Borders::ALL),
+        )
+        .x_axis(
+            Axis::default()
+                .title("session time")
+                .style(Style::default().fg(Color::Gray))
+                .labels(vec![Span::raw(clock(first)), Span::raw(clock(last))])
+                .bounds([x(first), x(last).max(x(first) + 1.0)]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("events/s")
+                .style(Style::default().fg(Color::Gray))
+                .labels(vec![
+                    Span::raw("0"),
+                    Span::raw(format!("{:.0}", top / 2.0)),
+                    Span::raw(format!("{top:.0}")),
+                ])
+                .bounds([0.0, top]),
+        );
+
+    if markers.is_empty() {
+        f.render_widget(chart, area);
+        return;
+    }
+
+    // The names of the markers go under the chart.
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(area);
+    let names: Vec<String> = markers
+        .iter()
+        .map(|marker| format!("{} {}", clock(marker.second), marker.name))
+        .collect();
+    f.render_widget(chart, parts[0]);
+    f.render_widget(
+        Paragraph::new(names.join(" · ")).style(Style::default().fg(Color::LightMagenta)),
+        parts[1],
+    );
+}
+
+// Color of a category's band in the traffic mix, each category has its own.
+fn mix_color(category: Category) -> Color {
+    match category {
+        Category::Keyboard => Color::LightCyan,
+        Category::Mouse => Color::LightMagenta,
+        Category::Focus => Color::LightGreen,
+        Category::Window => Color::LightYellow,
+        Category::Document => Color::LightBlue,
+        Category::Object => Color::Yellow,
+        Category::Terminal => Color::Cyan,
+        Category::Cache => Color::Magenta,
+        Category::Listeners => Color::Green,
+        Category::Available => Color::Blue,
+        Category::Other => Color::Gray,
+        Category::Error => Color::LightRed,
+    }
+}
+
+/// Events per second by category, stacked: each line is the top of its category's band,
+/// so the height between a line and the one below is that category's rate.
+fn render_mix(f: &mut Frame, area: Rect, app: &App) {
+    let seconds = app.category_series.seconds();
+    let now = seconds.len().saturating_sub(1) as f64;
+
+    // Only the categories that had events take a band.
+    let shown: Vec<usize> = (0..Category::COUNT)
+        .filter(|&i| seconds.iter().any(|second| second[i] > 0))
+        .collect();
+
+    let mut below = vec![0u64; seconds.len()];
+    let bands: Vec<(Category, Vec<(f64, f64)>)> = shown
+        .iter()
+        .map(|&i| {
+            let line = seconds
+                .iter()
+                .zip(below.iter_mut())
+                .enumerate()
+                .map(|(x, (second, below))| {
+                    *below += second[i];
+                    (x as f64 - now, *below as f64)
+                })
+                .collect();
+            (Category::ALL[i], line)
+        })
+        .collect();
+
+    // The top band first, so the legend reads like the chart.
+    let datasets = bands
+        .iter()
+        .rev()
+        .map(|(category, line)| {
+            Dataset::default()
+                .name(category.name())
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(mix_color(*category)))
+                .data(line)
+        })
+        .collect();
+
+    let top = below.iter().copied().max().unwrap_or(0).max(1) as f64 * 1.1;
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(format!(
+                    "Traffic mix, events per second by category, last {} seconds (Esc to close)",
+                    seconds.len()
+                ))
+                .border_style(Style::default().fg(Color::LightBlue))
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .borders(Borders::ALL),
+        )
+        .x_axis(
+            Axis::default()
+                .title("seconds")
+                .style(Style::default().fg(Color::Gray))
+                .labels(vec![Span::raw(format!("-{now}")), Span::raw("0")])
+                .bounds([-now.max(1.0), 0.0]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("events/s")
+                .style(Style::default().fg(Color::Gray))
+                .labels(vec![
+                    Span::raw("0"),
+                    Span::raw(format!("{:.0}", top / 2.0)),
+                    Span::raw(format!("{top:.0}")),
+                ])
+                .bounds([0.0, top]),
+        )
+        .hidden_legend_constraints((Constraint::Ratio(1, 3), Constraint::Ratio(2, 3)));
+
+    f.render_widget(chart, area);
+}
+
+/// A rectangle of `percent_x` by `percent_y` of `area`, centered in it.
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}